@@ -216,6 +216,28 @@ pub enum BinaryOperator {
     Plus,
 }
 
+impl std::fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            BinaryOperator::Division => "/",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterOrEqual => ">=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessOrEqual => "<=",
+            BinaryOperator::LogicAnd => "and",
+            BinaryOperator::LogicOr => "or",
+            BinaryOperator::Minus => "-",
+            BinaryOperator::Modulus => "%",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Plus => "+",
+        };
+
+        write!(f, "{symbol}")
+    }
+}
+
 #[derive(Clone, Debug, Copy)]
 pub struct Binary {
     pub operator: BinaryOperator,
@@ -262,6 +284,17 @@ pub enum UnaryOperator {
     Negation,
 }
 
+impl std::fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            UnaryOperator::Minus => "-",
+            UnaryOperator::Negation => "!",
+        };
+
+        write!(f, "{symbol}")
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Unary {
     pub operator: UnaryOperator,
@@ -305,4 +305,12 @@ impl Ast {
     pub fn main(&self) -> &[Stmt] {
         &self.initial_block
     }
+
+    pub fn main_mut(&mut self) -> &mut Vec<Stmt> {
+        &mut self.initial_block
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = FunctionId> {
+        (0..self.functions.len()).map(FunctionId::new)
+    }
 }
@@ -1,156 +1,199 @@
 use rlox_ast::Ast;
+use rlox_ast::expr::Expr;
 use rlox_ast::stmt::{self as ast_stmt, stmt_node};
 use rlox_infra::StructVec;
 
-use crate::{BasicBlockId, BasicBlockValue, ControlFlowGraph, EdgeKind, Edges};
+use crate::{BasicBlock, BasicBlockId, BasicBlockValue, ControlFlowGraph, EdgeKind, Procedure, Program, Terminator};
 
 struct State {
     block_label: usize,
     graph: ControlFlowGraph,
+    // Block receiving the statements, it is created lazily
+    // so consecutive statements end up in the same block.
+    open: Option<BasicBlockId>,
+    // When there is no open block, edges that go to the next one.
     parents: Vec<(BasicBlockId, EdgeKind)>,
 }
 
+impl State {
+    fn append(&mut self, stmt: BasicBlockValue) {
+        let open = self.open_block();
+        let basic_block: &mut BasicBlock = self.graph.get_mut(open);
+
+        basic_block.stmts.push(stmt);
+    }
+
+    fn open_block(&mut self) -> BasicBlockId {
+        if let Some(open) = self.open {
+            return open;
+        }
+
+        let fresh_block = self.graph.fresh_block();
+
+        for (parent, edge_kind) in std::mem::take(&mut self.parents) {
+            self.graph.add_edge(parent, edge_kind, fresh_block);
+        }
+
+        self.open = Some(fresh_block);
+        fresh_block
+    }
+
+    /// Closes the open block, returning the edges that leave the statements added so far.
+    fn leaves(&mut self) -> Vec<(BasicBlockId, EdgeKind)> {
+        match self.open.take() {
+            Some(open) => vec![(open, EdgeKind::Unconditional)],
+            None => std::mem::take(&mut self.parents),
+        }
+    }
+
+    /// Ends the open block with a branch on the condition of `stmt`.
+    fn branch(&mut self, stmt: ast_stmt::StmtId, condition: Expr) -> BasicBlockId {
+        let open = self.open_block();
+        let basic_block: &mut BasicBlock = self.graph.get_mut(open);
+
+        basic_block.terminator = Terminator::Branch(ast_stmt::StmtNode {
+            stmt_id: stmt,
+            inner: condition,
+        });
+
+        self.open = None;
+        open
+    }
+}
+
 pub fn from_sequence_of_stmts(sequence: &[ast_stmt::Stmt], ast: &Ast) -> ControlFlowGraph {
-    let mut graph = ControlFlowGraph::new();
-    let entry_id = graph.fresh_block(BasicBlockValue::EntryPoint);
-    let end_id = graph.fresh_block(BasicBlockValue::EndPoint);
+    let graph = ControlFlowGraph::new();
+    let entry_id = graph.entry_point();
+    let exit_id = graph.exit_point();
 
     let mut builder = State {
         block_label: 0,
         graph,
-        parents: vec![(entry_id, EdgeKind::Unconditional)],
+        open: Some(entry_id),
+        parents: vec![],
     };
 
     for stmt in sequence.iter().copied() {
-        let leaves = stmt_dispatch(stmt, ast, &mut builder);
-        builder.parents = leaves;
+        stmt_dispatch(stmt, ast, &mut builder);
     }
 
-    for (parent, edge_kind) in builder.parents {
-        let edges: &mut Edges = builder.graph.get_mut(parent);
-        edges.goes_to.push(end_id);
-        edges.edge_kind.push(edge_kind);
+    for (parent, edge_kind) in builder.leaves() {
+        builder.graph.add_edge(parent, edge_kind, exit_id);
     }
 
     builder.graph
 }
 
-fn stmt_dispatch(stmt: ast_stmt::Stmt, ast: &Ast, builder: &mut State) -> Vec<(BasicBlockId, EdgeKind)> {
+/// Builds the graph of the top level statements followed by one graph per function body.
+pub fn from_program(ast: &Ast) -> Program {
+    let mut procedures = vec![(Procedure::Main, from_sequence_of_stmts(ast.main(), ast))];
+
+    for function in ast.functions() {
+        let body = &ast[ast[function].body];
+        procedures.push((Procedure::Function(function), from_sequence_of_stmts(body, ast)));
+    }
+
+    Program {
+        procedures,
+    }
+}
+
+fn stmt_dispatch(stmt: ast_stmt::Stmt, ast: &Ast, builder: &mut State) {
     match stmt.kind() {
-        ast_stmt::StmtKind::Declaration(inner) => emit_singleton_block(stmt_node!(stmt, inner), builder),
-        ast_stmt::StmtKind::Expr(inner) => emit_singleton_block(stmt_node!(stmt, inner), builder),
+        ast_stmt::StmtKind::Declaration(inner) => builder.append(stmt_node!(stmt, inner).into()),
+        ast_stmt::StmtKind::Expr(inner) => builder.append(stmt_node!(stmt, inner).into()),
         ast_stmt::StmtKind::Block(inner) => block_dispatch(inner, ast, builder),
-        ast_stmt::StmtKind::IfElse(inner) => branch_dispatch(inner, ast, builder),
-        ast_stmt::StmtKind::While(inner) => while_dispatch(inner, ast, builder),
+        ast_stmt::StmtKind::IfElse(inner) => branch_dispatch(stmt_node!(stmt, inner), ast, builder),
+        ast_stmt::StmtKind::While(inner) => while_dispatch(stmt_node!(stmt, inner), ast, builder),
     }
 }
 
-fn while_dispatch(id: ast_stmt::WhileId, ast: &Ast, builder: &mut State) -> Vec<(BasicBlockId, EdgeKind)> {
-    let data = &ast[id];
-    let loop_header = builder.graph.fresh_block(data.condition.into());
+fn while_dispatch(node: ast_stmt::StmtNode<ast_stmt::WhileId>, ast: &Ast, builder: &mut State) {
+    let data = &ast[node.inner];
 
-    for (parent, edge_kind) in builder.parents.iter().copied() {
-        let edges: &mut Edges = builder.graph.get_mut(parent);
-        edges.edge_kind.push(edge_kind);
-        edges.goes_to.push(loop_header);
-    }
+    // The loop header is the target of the back edge, so it can not
+    // share a block with the statements that come before the loop.
+    builder.parents = builder.leaves();
 
-    builder.parents[0] = (loop_header, EdgeKind::True);
-    builder.parents.truncate(1);
+    let loop_header = builder.branch(node.stmt_id, data.condition);
+    builder.parents = vec![(loop_header, EdgeKind::True)];
 
-    let body_leaves = stmt_dispatch(data.body, ast, builder);
+    stmt_dispatch(data.body, ast, builder);
 
-    for (leaf, _) in body_leaves.iter().copied() {
-        let edges: &mut Edges = builder.graph.get_mut(leaf);
-        edges.edge_kind.push(EdgeKind::Unconditional);
-        edges.goes_to.push(loop_header);
+    for (leaf, edge_kind) in builder.leaves() {
+        builder.graph.add_edge(leaf, edge_kind, loop_header);
     }
 
-    vec![(loop_header, EdgeKind::False)]
+    builder.parents = vec![(loop_header, EdgeKind::False)];
 }
 
-fn branch_dispatch(id: ast_stmt::IfElseId, ast: &Ast, builder: &mut State) -> Vec<(BasicBlockId, EdgeKind)> {
-    let data = &ast[id];
+fn branch_dispatch(node: ast_stmt::StmtNode<ast_stmt::IfElseId>, ast: &Ast, builder: &mut State) {
+    let data = &ast[node.inner];
 
-    let condition = builder.graph.fresh_block(data.condition.into());
+    let condition = builder.branch(node.stmt_id, data.condition);
+    builder.parents = vec![(condition, EdgeKind::True)];
 
-    for (parent, edge_kind) in builder.parents.iter().copied() {
-        let edges: &mut Edges = builder.graph.get_mut(parent);
-        edges.edge_kind.push(edge_kind);
-        edges.goes_to.push(condition);
-    }
+    stmt_dispatch(data.if_branch, ast, builder);
 
-    builder.parents[0] = (condition, EdgeKind::True);
-    builder.parents.truncate(1);
-
-    let if_leaves = stmt_dispatch(data.if_branch, ast, builder);
+    let mut leaves = builder.leaves();
 
     let Some(else_branch) = data.else_branch else {
-        let mut leaves = if_leaves;
         leaves.push((condition, EdgeKind::False));
+        builder.parents = leaves;
 
-        return leaves;
+        return;
     };
 
-    builder.parents[0] = (condition, EdgeKind::False);
-    builder.parents.truncate(1);
+    builder.parents = vec![(condition, EdgeKind::False)];
 
-    let else_leaves = stmt_dispatch(else_branch, ast, builder);
+    stmt_dispatch(else_branch, ast, builder);
 
-    let mut leaves = if_leaves;
-    leaves.extend(else_leaves);
-
-    leaves
+    leaves.extend(builder.leaves());
+    builder.parents = leaves;
 }
 
-fn block_dispatch(id: ast_stmt::BlockId, ast: &Ast, builder: &mut State) -> Vec<(BasicBlockId, EdgeKind)> {
+fn block_dispatch(id: ast_stmt::BlockId, ast: &Ast, builder: &mut State) {
     let block_label = builder.block_label;
     builder.block_label += 1;
 
-    let enter_block = builder
-        .graph
-        .fresh_block(BasicBlockValue::EnterBlock(block_label));
+    builder.append(BasicBlockValue::EnterBlock(block_label));
 
-    for (parent, edge_kind) in builder.parents.iter().copied() {
-        let edges: &mut Edges = builder.graph.get_mut(parent);
-        edges.edge_kind.push(edge_kind);
-        edges.goes_to.push(enter_block);
+    for stmt in ast[id].iter().copied() {
+        stmt_dispatch(stmt, ast, builder);
     }
 
-    // This access is safe because there is always at least
-    // one parent node (except for the entry point).
-    builder.parents[0] = (enter_block, EdgeKind::Unconditional);
-    builder.parents.truncate(1);
+    builder.append(BasicBlockValue::LeaveBlock(block_label));
+}
 
-    for stmt in ast[id].iter().copied() {
-        let leaves = stmt_dispatch(stmt, ast, builder);
-        builder.parents = leaves;
-    }
+#[cfg(test)]
+mod tests {
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
 
-    let leave_block = builder
-        .graph
-        .fresh_block(BasicBlockValue::LeaveBlock(block_label));
+    use super::*;
 
-    for (parent, edge_kind) in builder.parents.iter().copied() {
-        let edges: &mut Edges = builder.graph.get_mut(parent);
-        edges.edge_kind.push(edge_kind);
-        edges.goes_to.push(leave_block);
-    }
+    fn build(code: &str) -> ControlFlowGraph {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
 
-    vec![(leave_block, EdgeKind::Unconditional)]
-}
+        from_sequence_of_stmts(ast.main(), &ast)
+    }
 
-fn emit_singleton_block<S>(stmt: S, builder: &mut State) -> Vec<(BasicBlockId, EdgeKind)>
-where
-    BasicBlockValue: From<S>,
-{
-    let fresh_block = builder.graph.fresh_block(stmt.into());
+    #[test]
+    fn straight_line_code_is_one_block() {
+        let graph = build("var a = 1; { var b = a; a = b; } a = 2;");
+        let entry: &BasicBlock = graph.get(graph.entry_point());
 
-    for (parent, edge_kind) in builder.parents.iter().copied() {
-        let edges: &mut Edges = builder.graph.get_mut(parent);
-        edges.goes_to.push(fresh_block);
-        edges.edge_kind.push(edge_kind);
+        assert_eq!(graph.basic_block_ids().count(), 2);
+        assert_eq!(entry.stmts.len(), 6);
     }
 
-    vec![(fresh_block, EdgeKind::Unconditional)]
+    #[test]
+    fn loop_header_gets_its_own_block() {
+        let graph = build("var a = 1; while a < 10 { a = a + 1; } a = 0;");
+
+        // Entry, exit, header, body and the statements after the loop.
+        assert_eq!(graph.basic_block_ids().count(), 5);
+    }
 }
@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+
+use rlox_ast::expr::{Expr, ExprKind};
+use rlox_ast::{Ast, FunctionId};
+use rlox_infra::StructVec;
+
+use crate::{BasicBlock, BasicBlockId, BasicBlockValue, ControlFlowGraph, Procedure, Program, Terminator};
+
+/// A call whose callee is known without running the program.
+#[derive(Debug, Clone, Copy)]
+pub struct CallSite {
+    pub caller: Procedure,
+    pub callee: FunctionId,
+    // Basic block of the caller that contains the call.
+    pub block: BasicBlockId,
+    pub call: Expr,
+}
+
+#[derive(Debug, Default)]
+pub struct CallGraph {
+    pub calls: Vec<CallSite>,
+}
+
+impl CallGraph {
+    pub fn callees(&self, caller: Procedure) -> impl Iterator<Item = &CallSite> {
+        self.calls.iter().filter(move |call| call.caller == caller)
+    }
+
+    pub fn callers(&self, callee: FunctionId) -> impl Iterator<Item = &CallSite> {
+        self.calls.iter().filter(move |call| call.callee == callee)
+    }
+}
+
+/// A call resolves statically when its callee is an identifier naming exactly one function,
+/// and the caller does not declare a variable or parameter that could shadow it.
+pub fn build(program: &Program, ast: &Ast) -> CallGraph {
+    let mut call_graph = CallGraph::default();
+
+    for (caller, cf_graph) in program.procedures.iter() {
+        let shadowed = declared_names(*caller, cf_graph, ast);
+
+        for bb_id in cf_graph.basic_block_ids() {
+            for call in calls_in_block(cf_graph.get(bb_id), ast) {
+                let Some(callee) = resolve(call, &shadowed, ast) else {
+                    continue;
+                };
+
+                call_graph.calls.push(CallSite {
+                    caller: *caller,
+                    callee,
+                    block: bb_id,
+                    call,
+                });
+            }
+        }
+    }
+
+    call_graph
+}
+
+fn resolve(call: Expr, shadowed: &HashSet<&str>, ast: &Ast) -> Option<FunctionId> {
+    let ExprKind::Call(call) = call.kind() else {
+        return None;
+    };
+
+    let ExprKind::Identifier(identifier) = ast[call].lhs.kind() else {
+        return None;
+    };
+
+    let name = &ast[identifier];
+
+    if shadowed.contains(name) {
+        return None;
+    }
+
+    let mut candidates = ast
+        .functions()
+        .filter(|function| &ast[ast[*function].name] == name);
+
+    match (candidates.next(), candidates.next()) {
+        (Some(function), None) => Some(function),
+        _ => None,
+    }
+}
+
+fn declared_names<'a>(procedure: Procedure, cf_graph: &ControlFlowGraph, ast: &'a Ast) -> HashSet<&'a str> {
+    let mut names = HashSet::new();
+
+    if let Procedure::Function(function) = procedure {
+        names.extend(ast[function].params.iter().map(|param| &ast[*param]));
+    }
+
+    for bb_id in cf_graph.basic_block_ids() {
+        let basic_block: &BasicBlock = cf_graph.get(bb_id);
+
+        for stmt in basic_block.stmts.iter() {
+            if let BasicBlockValue::Declaration(node) = stmt {
+                names.insert(&ast[ast[node.inner].identifier]);
+            }
+        }
+    }
+
+    names
+}
+
+fn calls_in_block(basic_block: &BasicBlock, ast: &Ast) -> Vec<Expr> {
+    let mut calls = Vec::new();
+
+    for stmt in basic_block.stmts.iter() {
+        match stmt {
+            BasicBlockValue::Declaration(node) => {
+                if let Some(value) = ast[node.inner].value {
+                    calls_in_expr(value, ast, &mut calls);
+                }
+            }
+
+            BasicBlockValue::StmtExpr(node) => calls_in_expr(node.inner, ast, &mut calls),
+
+            BasicBlockValue::EnterBlock(_) | BasicBlockValue::LeaveBlock(_) => (),
+        }
+    }
+
+    if let Terminator::Branch(node) = basic_block.terminator {
+        calls_in_expr(node.inner, ast, &mut calls);
+    }
+
+    calls
+}
+
+fn calls_in_expr(expr: Expr, ast: &Ast, calls: &mut Vec<Expr>) {
+    match expr.kind() {
+        ExprKind::Assign(inner) => {
+            calls_in_expr(ast[inner].lhs, ast, calls);
+            calls_in_expr(ast[inner].rhs, ast, calls);
+        }
+
+        ExprKind::Binary(inner) => {
+            calls_in_expr(ast[inner].lhs, ast, calls);
+            calls_in_expr(ast[inner].rhs, ast, calls);
+        }
+
+        ExprKind::Unary(inner) => calls_in_expr(ast[inner].operand, ast, calls),
+
+        ExprKind::Call(inner) => {
+            calls_in_expr(ast[inner].lhs, ast, calls);
+
+            for argument in ast[inner].arguments.iter().copied() {
+                calls_in_expr(argument, ast, calls);
+            }
+
+            calls.push(expr);
+        }
+
+        ExprKind::Identifier(_)
+        | ExprKind::String(_)
+        | ExprKind::Natural(_)
+        | ExprKind::Decimal(_)
+        | ExprKind::Boolean(_)
+        | ExprKind::Nil => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_ast::expr::Call;
+    use rlox_ast::stmt::{Stmt, StmtKind};
+    use rlox_ast::{AstElem, Function, Identifier};
+
+    use super::*;
+    use crate::build_cfg;
+
+    // Functions can not be parsed yet, so the calls are added by hand.
+    fn call_stmt(ast: &mut Ast, callee: &str) -> Stmt {
+        let identifier: Identifier = ast.add(callee.as_bytes());
+        let lhs: Expr = ast.add(identifier);
+        let call: Expr = ast.add(Call {
+            lhs,
+            arguments: vec![],
+        });
+
+        ast.add(call)
+    }
+
+    #[test]
+    fn calls_resolve_to_functions() {
+        let mut ast = Ast::default();
+
+        let recursive_call = call_stmt(&mut ast, "f");
+        let body: Stmt = ast.add([recursive_call].as_slice());
+        let StmtKind::Block(body) = body.kind() else {
+            unreachable!()
+        };
+
+        let name: Identifier = ast.add(b"f".as_slice());
+        let function = ast.add(Function {
+            name,
+            params: vec![],
+            body,
+        });
+
+        let main_call = call_stmt(&mut ast, "f");
+        let native_call = call_stmt(&mut ast, "println");
+        ast.push_into_initial_block(main_call);
+        ast.push_into_initial_block(native_call);
+
+        let program = build_cfg::from_program(&ast);
+        let call_graph = build(&program, &ast);
+
+        assert_eq!(call_graph.calls.len(), 2);
+        assert_eq!(call_graph.callees(Procedure::Main).count(), 1);
+        assert_eq!(call_graph.callees(Procedure::Function(function)).count(), 1);
+        assert_eq!(call_graph.callers(function).count(), 2);
+    }
+}
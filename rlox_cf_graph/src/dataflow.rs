@@ -0,0 +1,205 @@
+//! Worklist solver for dataflow analyses over basic blocks. An analysis only
+//! describes its facts, how they join and how a block transforms them, the
+//! solver takes care of iterating until nothing changes.
+
+use std::collections::{BTreeSet, VecDeque};
+
+use rlox_infra::StructVec;
+
+use crate::{BasicBlockId, CfgVec, ControlFlowGraph};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Facts flow from the entry point following the edges.
+    Forward,
+    /// Facts flow from the exit point against the edges.
+    Backward,
+}
+
+pub trait Lattice: Clone + PartialEq {
+    /// Merges the facts of another path into these ones.
+    fn join(&mut self, other: &Self);
+}
+
+/// Sets join by union, the usual choice for "may" analyses.
+impl<T: Ord + Clone> Lattice for BTreeSet<T> {
+    fn join(&mut self, other: &Self) {
+        self.extend(other.iter().cloned());
+    }
+}
+
+pub trait Analysis {
+    type Fact: Lattice;
+
+    const DIRECTION: Direction;
+
+    /// Initial facts of every block, joining with them must not change anything.
+    fn bottom(&self) -> Self::Fact;
+
+    /// Facts at the entry point for forward analyses, or at the exit point for backward ones.
+    fn boundary(&self) -> Self::Fact;
+
+    /// Facts after running the block, or before it for backward analyses.
+    fn transfer(&self, id: BasicBlockId, input: &Self::Fact) -> Self::Fact;
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Point {
+    In(BasicBlockId),
+    Out(BasicBlockId),
+}
+
+#[derive(Debug, Clone)]
+pub struct Facts<F> {
+    ins: CfgVec<F>,
+    outs: CfgVec<F>,
+}
+
+impl<F> StructVec<F, Point> for Facts<F> {
+    fn assign(&mut self, id: Point, property: F) {
+        *self.get_mut(id) = property;
+    }
+
+    fn get(&self, id: Point) -> &F {
+        match id {
+            Point::In(bb_id) => &self.ins[bb_id],
+            Point::Out(bb_id) => &self.outs[bb_id],
+        }
+    }
+
+    fn get_mut(&mut self, id: Point) -> &mut F {
+        match id {
+            Point::In(bb_id) => &mut self.ins[bb_id],
+            Point::Out(bb_id) => &mut self.outs[bb_id],
+        }
+    }
+}
+
+pub fn solve<A: Analysis>(analysis: &A, cf_graph: &ControlFlowGraph) -> Facts<A::Fact> {
+    let mut facts = Facts {
+        ins: CfgVec::filled(analysis.bottom(), cf_graph),
+        outs: CfgVec::filled(analysis.bottom(), cf_graph),
+    };
+
+    let boundary = match A::DIRECTION {
+        Direction::Forward => cf_graph.entry_point(),
+        Direction::Backward => cf_graph.exit_point(),
+    };
+
+    // Blocks are visited in creation order, which roughly follows the source
+    // code, so forward analyses usually converge in a few iterations.
+    let mut worklist: VecDeque<_> = match A::DIRECTION {
+        Direction::Forward => cf_graph.basic_block_ids().collect(),
+        Direction::Backward => cf_graph.basic_block_ids().rev().collect(),
+    };
+
+    let mut queued = CfgVec::filled(true, cf_graph);
+
+    while let Some(id) = worklist.pop_front() {
+        queued[id] = false;
+
+        let (input, output, sources, targets) = match A::DIRECTION {
+            Direction::Forward => (Point::In(id), Point::Out(id), cf_graph.predecessors(id), cf_graph.successors(id)),
+            Direction::Backward => (Point::Out(id), Point::In(id), cf_graph.successors(id), cf_graph.predecessors(id)),
+        };
+
+        let mut joined = match id == boundary {
+            true => analysis.boundary(),
+            false => analysis.bottom(),
+        };
+
+        for source in sources.iter().copied() {
+            let source_output = match A::DIRECTION {
+                Direction::Forward => Point::Out(source),
+                Direction::Backward => Point::In(source),
+            };
+
+            joined.join(facts.get(source_output));
+        }
+
+        let transferred = analysis.transfer(id, &joined);
+        facts.assign(input, joined);
+
+        if *facts.get(output) == transferred {
+            continue;
+        }
+
+        facts.assign(output, transferred);
+
+        for target in targets.iter().copied() {
+            if !queued[target] {
+                queued[target] = true;
+                worklist.push_back(target);
+            }
+        }
+    }
+
+    facts
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_ast::Ast;
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use super::*;
+    use crate::build_cfg;
+    use crate::variables::{self, Access, VarId, Variables};
+
+    /// Variables written in some path from the entry point.
+    struct MaybeWritten<'a> {
+        cf_graph: &'a ControlFlowGraph,
+        variables: &'a Variables,
+        ast: &'a Ast,
+    }
+
+    impl Analysis for MaybeWritten<'_> {
+        type Fact = BTreeSet<VarId>;
+
+        const DIRECTION: Direction = Direction::Forward;
+
+        fn bottom(&self) -> Self::Fact {
+            BTreeSet::new()
+        }
+
+        fn boundary(&self) -> Self::Fact {
+            BTreeSet::new()
+        }
+
+        fn transfer(&self, id: BasicBlockId, input: &Self::Fact) -> Self::Fact {
+            let mut output = input.clone();
+
+            for access in variables::accesses(self.cf_graph.get(id), self.variables, self.ast) {
+                if let Access::Write(_, var) = access {
+                    output.insert(var);
+                }
+            }
+
+            output
+        }
+    }
+
+    #[test]
+    fn facts_flow_through_loops() {
+        let code = "var a = 0; var b; while a < 10 { a = a + 1; b = a; }";
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        let cf_graph = build_cfg::from_sequence_of_stmts(ast.main(), &ast);
+        let variables = variables::resolve(&ast);
+        let analysis = MaybeWritten {
+            cf_graph: &cf_graph,
+            variables: &variables,
+            ast: &ast,
+        };
+
+        let facts = solve(&analysis, &cf_graph);
+        let all: BTreeSet<_> = variables.ids().collect();
+
+        assert_eq!(facts.get(Point::In(cf_graph.entry_point())), &BTreeSet::new());
+        assert_eq!(facts.get(Point::Out(cf_graph.entry_point())), &all);
+        assert_eq!(facts.get(Point::In(cf_graph.exit_point())), &all);
+    }
+}
@@ -0,0 +1,294 @@
+//! Dominance as described in "A Simple, Fast Dominance Algorithm"
+//! by Cooper, Harvey and Kennedy. Post-dominance is the same
+//! computation over the graph with its edges reversed.
+
+use crate::dataflow::Direction;
+use crate::{BasicBlockId, CfgVec, ControlFlowGraph};
+
+#[derive(Debug, Clone)]
+pub struct DominatorTree {
+    direction: Direction,
+    root: BasicBlockId,
+    // Blocks not reachable from the root have no immediate dominator.
+    immediate: CfgVec<Option<BasicBlockId>>,
+    children: CfgVec<Vec<BasicBlockId>>,
+}
+
+impl DominatorTree {
+    pub fn root(&self) -> BasicBlockId {
+        self.root
+    }
+
+    /// The root is its own immediate dominator.
+    pub fn immediate_dominator(&self, id: BasicBlockId) -> Option<BasicBlockId> {
+        self.immediate[id]
+    }
+
+    pub fn children(&self, id: BasicBlockId) -> &[BasicBlockId] {
+        &self.children[id]
+    }
+
+    pub fn is_reachable(&self, id: BasicBlockId) -> bool {
+        self.immediate[id].is_some()
+    }
+
+    /// Every block dominates itself.
+    pub fn dominates(&self, dominator: BasicBlockId, id: BasicBlockId) -> bool {
+        let mut current = id;
+
+        loop {
+            if current == dominator {
+                return true;
+            }
+
+            match self.immediate[current] {
+                Some(parent) if parent != current => current = parent,
+                _ => return false,
+            }
+        }
+    }
+
+    /// Blocks where the dominance of each block ends. For a post-dominator
+    /// tree these are the blocks each block is control dependent on.
+    pub fn frontiers(&self, cf_graph: &ControlFlowGraph) -> DominanceFrontiers {
+        let mut frontiers = CfgVec::filled(Vec::new(), cf_graph);
+
+        for id in cf_graph.basic_block_ids() {
+            let Some(immediate) = self.immediate[id] else {
+                continue;
+            };
+
+            let joins = incoming(self.direction, id, cf_graph);
+
+            if joins.len() < 2 {
+                continue;
+            }
+
+            for runner in joins.iter().copied() {
+                let mut runner = runner;
+
+                while self.is_reachable(runner) && runner != immediate {
+                    let frontier: &mut Vec<BasicBlockId> = &mut frontiers[runner];
+
+                    if !frontier.contains(&id) {
+                        frontier.push(id);
+                    }
+
+                    runner = self.immediate[runner].unwrap_or(immediate);
+                }
+            }
+        }
+
+        DominanceFrontiers {
+            frontiers,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DominanceFrontiers {
+    frontiers: CfgVec<Vec<BasicBlockId>>,
+}
+
+impl DominanceFrontiers {
+    pub fn get(&self, id: BasicBlockId) -> &[BasicBlockId] {
+        &self.frontiers[id]
+    }
+}
+
+pub fn dominators(cf_graph: &ControlFlowGraph) -> DominatorTree {
+    compute(Direction::Forward, cf_graph.entry_point(), cf_graph)
+}
+
+pub fn post_dominators(cf_graph: &ControlFlowGraph) -> DominatorTree {
+    compute(Direction::Backward, cf_graph.exit_point(), cf_graph)
+}
+
+fn outgoing(direction: Direction, id: BasicBlockId, cf_graph: &ControlFlowGraph) -> &[BasicBlockId] {
+    match direction {
+        Direction::Forward => cf_graph.successors(id),
+        Direction::Backward => cf_graph.predecessors(id),
+    }
+}
+
+fn incoming(direction: Direction, id: BasicBlockId, cf_graph: &ControlFlowGraph) -> &[BasicBlockId] {
+    match direction {
+        Direction::Forward => cf_graph.predecessors(id),
+        Direction::Backward => cf_graph.successors(id),
+    }
+}
+
+fn compute(direction: Direction, root: BasicBlockId, cf_graph: &ControlFlowGraph) -> DominatorTree {
+    let postorder = postorder(direction, root, cf_graph);
+
+    let mut order = CfgVec::filled(None, cf_graph);
+    for (position, id) in postorder.iter().copied().enumerate() {
+        order[id] = Some(position);
+    }
+
+    let mut immediate = CfgVec::filled(None, cf_graph);
+    immediate[root] = Some(root);
+
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for id in postorder.iter().rev().copied().filter(|id| *id != root) {
+            let mut new_immediate = None;
+
+            for candidate in incoming(direction, id, cf_graph).iter().copied() {
+                if immediate[candidate].is_none() {
+                    continue;
+                }
+
+                new_immediate = match new_immediate {
+                    None => Some(candidate),
+                    Some(current) => Some(intersect(candidate, current, &immediate, &order)),
+                };
+            }
+
+            if new_immediate != immediate[id] {
+                immediate[id] = new_immediate;
+                changed = true;
+            }
+        }
+    }
+
+    let mut children = CfgVec::filled(Vec::new(), cf_graph);
+
+    for id in cf_graph.basic_block_ids() {
+        match immediate[id] {
+            Some(parent) if parent != id => children[parent].push(id),
+            _ => (),
+        }
+    }
+
+    DominatorTree {
+        direction,
+        root,
+        immediate,
+        children,
+    }
+}
+
+/// Closest common dominator of both blocks, walking up the tree
+/// using the postorder numbers: dominators always come later.
+fn intersect(
+    lhs: BasicBlockId,
+    rhs: BasicBlockId,
+    immediate: &CfgVec<Option<BasicBlockId>>,
+    order: &CfgVec<Option<usize>>,
+) -> BasicBlockId {
+    let mut lhs = lhs;
+    let mut rhs = rhs;
+
+    // Only blocks with an immediate dominator get here, and those have been numbered.
+    let position = |id: BasicBlockId| order[id].unwrap_or_default();
+
+    while lhs != rhs {
+        while position(lhs) < position(rhs) {
+            lhs = immediate[lhs].unwrap_or(lhs);
+        }
+
+        while position(rhs) < position(lhs) {
+            rhs = immediate[rhs].unwrap_or(rhs);
+        }
+    }
+
+    lhs
+}
+
+fn postorder(direction: Direction, root: BasicBlockId, cf_graph: &ControlFlowGraph) -> Vec<BasicBlockId> {
+    let mut visited = CfgVec::filled(false, cf_graph);
+    let mut postorder = Vec::new();
+
+    // Each entry is a block and the index of the next edge to follow.
+    let mut stack = vec![(root, 0)];
+    visited[root] = true;
+
+    while let Some((id, next)) = stack.pop() {
+        let Some(target) = outgoing(direction, id, cf_graph).get(next).copied() else {
+            postorder.push(id);
+            continue;
+        };
+
+        stack.push((id, next + 1));
+
+        if !visited[target] {
+            visited[target] = true;
+            stack.push((target, 0));
+        }
+    }
+
+    postorder
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use super::*;
+    use crate::build_cfg;
+
+    fn build(code: &str) -> ControlFlowGraph {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        build_cfg::from_sequence_of_stmts(ast.main(), &ast)
+    }
+
+    fn ids(cf_graph: &ControlFlowGraph) -> Vec<BasicBlockId> {
+        cf_graph.basic_block_ids().collect()
+    }
+
+    #[test]
+    fn diamond() {
+        // 0: branch, 2: if, 3: else, 4: join, 1: exit.
+        let cf_graph = build("var a = 1; if a == 1 { a = 2; } else { a = 3; } a = 4;");
+        let [entry, exit, if_branch, else_branch, join] = ids(&cf_graph)[..] else {
+            panic!("unexpected number of blocks");
+        };
+
+        let tree = dominators(&cf_graph);
+        assert_eq!(tree.immediate_dominator(entry), Some(entry));
+        assert_eq!(tree.immediate_dominator(if_branch), Some(entry));
+        assert_eq!(tree.immediate_dominator(else_branch), Some(entry));
+        assert_eq!(tree.immediate_dominator(join), Some(entry));
+        assert_eq!(tree.immediate_dominator(exit), Some(join));
+        assert!(!tree.dominates(if_branch, join));
+
+        let frontiers = tree.frontiers(&cf_graph);
+        assert_eq!(frontiers.get(if_branch), &[join]);
+        assert_eq!(frontiers.get(else_branch), &[join]);
+        assert!(frontiers.get(entry).is_empty());
+
+        let post_tree = post_dominators(&cf_graph);
+        assert_eq!(post_tree.immediate_dominator(entry), Some(join));
+        assert_eq!(post_tree.immediate_dominator(if_branch), Some(join));
+
+        let control_dependence = post_tree.frontiers(&cf_graph);
+        assert_eq!(control_dependence.get(if_branch), &[entry]);
+        assert!(control_dependence.get(join).is_empty());
+    }
+
+    #[test]
+    fn loop_header_is_in_its_own_frontier() {
+        // 0: entry, 2: header, 3: body, 1: exit.
+        let cf_graph = build("var a = 1; while a < 10 { a = a + 1; }");
+        let [entry, exit, header, body] = ids(&cf_graph)[..] else {
+            panic!("unexpected number of blocks");
+        };
+
+        let tree = dominators(&cf_graph);
+        assert_eq!(tree.immediate_dominator(header), Some(entry));
+        assert_eq!(tree.immediate_dominator(body), Some(header));
+        assert_eq!(tree.immediate_dominator(exit), Some(header));
+
+        let frontiers = tree.frontiers(&cf_graph);
+        assert_eq!(frontiers.get(body), &[header]);
+        assert_eq!(frontiers.get(header), &[header]);
+    }
+}
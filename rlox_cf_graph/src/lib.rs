@@ -1,15 +1,36 @@
 pub mod build_cfg;
-
+pub mod call_graph;
+pub mod dataflow;
+pub mod dominators;
+pub mod liveness;
+pub mod loops;
+pub mod reachability;
+pub mod reaching_definitions;
+pub mod ssa;
+pub mod variables;
+
+use std::cell::OnceCell;
 use std::ops::{Index, IndexMut};
 
+use rlox_ast::FunctionId;
 use rlox_ast::expr::Expr;
 use rlox_ast::stmt::{self, StmtNode};
 use rlox_infra::StructVec;
 
+#[derive(Debug, Clone)]
 pub struct CfgVec<T> {
     inner: Vec<T>,
 }
 
+impl<T: Clone> CfgVec<T> {
+    /// One `value` for every basic block of the graph.
+    pub fn filled(value: T, cf_graph: &ControlFlowGraph) -> CfgVec<T> {
+        CfgVec {
+            inner: vec![value; cf_graph.nodes.len()],
+        }
+    }
+}
+
 impl<T> Index<BasicBlockId> for CfgVec<T> {
     type Output = T;
 
@@ -43,24 +64,15 @@ impl BasicBlockId {
     }
 }
 
+/// Statements that can be part of a basic block, they never change the control flow.
 #[derive(Debug, Clone, Copy)]
 pub enum BasicBlockValue {
     Declaration(StmtNode<stmt::DeclarationId>),
     StmtExpr(StmtNode<Expr>),
-    Condition(Expr),
 
-    // Block values have a unique value identifying them.
+    // Scope annotations, block values have a unique value identifying them.
     EnterBlock(usize),
     LeaveBlock(usize),
-
-    EntryPoint,
-    EndPoint,
-}
-
-impl From<Expr> for BasicBlockValue {
-    fn from(value: Expr) -> Self {
-        BasicBlockValue::Condition(value)
-    }
 }
 
 impl From<StmtNode<stmt::DeclarationId>> for BasicBlockValue {
@@ -75,15 +87,29 @@ impl From<StmtNode<Expr>> for BasicBlockValue {
     }
 }
 
+/// How the execution continues once the statements of a basic block are done.
+#[derive(Debug, Clone, Copy)]
+pub enum Terminator {
+    /// Goes to the true or false edge depending on the condition of
+    /// the if-else or while statement given by the node.
+    Branch(StmtNode<Expr>),
+    /// Goes to the only edge of the block.
+    Jump,
+    /// Ends the execution, only the exit point has this terminator.
+    Exit,
+}
+
 #[derive(Debug, Clone)]
 pub struct BasicBlock {
-    pub stmt: BasicBlockValue,
+    pub stmts: Vec<BasicBlockValue>,
+    pub terminator: Terminator,
 }
 
 impl BasicBlock {
-    fn new(stmt: BasicBlockValue) -> BasicBlock {
+    fn new(terminator: Terminator) -> BasicBlock {
         BasicBlock {
-            stmt,
+            stmts: Vec::new(),
+            terminator,
         }
     }
 }
@@ -143,10 +169,12 @@ impl Edges {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct ControlFlowGraph {
     nodes: Vec<BasicBlock>,
     edges: Vec<Edges>,
+    /// Built from `edges` when first needed, every change to the edges drops it.
+    predecessors: OnceCell<Vec<Vec<BasicBlockId>>>,
 }
 
 impl StructVec<BasicBlock, BasicBlockId> for ControlFlowGraph {
@@ -165,6 +193,7 @@ impl StructVec<BasicBlock, BasicBlockId> for ControlFlowGraph {
 
 impl StructVec<Edges, BasicBlockId> for ControlFlowGraph {
     fn assign(&mut self, id: BasicBlockId, item: Edges) {
+        self.predecessors.take();
         self.edges[id.inner] = item;
     }
 
@@ -173,25 +202,126 @@ impl StructVec<Edges, BasicBlockId> for ControlFlowGraph {
     }
 
     fn get_mut(&mut self, id: BasicBlockId) -> &mut Edges {
+        self.predecessors.take();
         &mut self.edges[id.inner]
     }
 }
 
+impl Default for ControlFlowGraph {
+    fn default() -> Self {
+        ControlFlowGraph::new()
+    }
+}
+
 impl ControlFlowGraph {
+    /// Every graph starts with an empty entry point and an exit point.
     pub fn new() -> ControlFlowGraph {
-        ControlFlowGraph::default()
+        let mut graph = ControlFlowGraph {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            predecessors: OnceCell::new(),
+        };
+
+        graph.fresh_block();
+        let exit_point = graph.fresh_block();
+        graph.nodes[exit_point.inner].terminator = Terminator::Exit;
+
+        graph
     }
 
-    pub fn fresh_block(&mut self, stmt: BasicBlockValue) -> BasicBlockId {
+    pub fn entry_point(&self) -> BasicBlockId {
+        BasicBlockId::new(0)
+    }
+
+    pub fn exit_point(&self) -> BasicBlockId {
+        BasicBlockId::new(1)
+    }
+
+    /// Creates an empty basic block that jumps, its edges must be added by the caller.
+    pub fn fresh_block(&mut self) -> BasicBlockId {
         let block_id = BasicBlockId::new(self.nodes.len());
 
-        self.nodes.push(BasicBlock::new(stmt));
+        self.nodes.push(BasicBlock::new(Terminator::Jump));
         self.edges.push(Edges::default());
+        self.predecessors.take();
 
         block_id
     }
 
-    pub fn basic_block_ids(&self) -> impl Iterator<Item = BasicBlockId> {
+    pub fn add_edge(&mut self, from: BasicBlockId, edge_kind: EdgeKind, to: BasicBlockId) {
+        let edges = &mut self.edges[from.inner];
+
+        edges.edge_kind.push(edge_kind);
+        edges.goes_to.push(to);
+
+        self.predecessors.take();
+    }
+
+    pub fn successors(&self, id: BasicBlockId) -> &[BasicBlockId] {
+        &self.edges[id.inner].goes_to
+    }
+
+    /// Blocks with an edge going to `id`, once per edge.
+    pub fn predecessors(&self, id: BasicBlockId) -> &[BasicBlockId] {
+        let predecessors = self.predecessors.get_or_init(|| {
+            let mut predecessors = vec![Vec::new(); self.nodes.len()];
+
+            for (from, edges) in self.edges.iter().enumerate() {
+                for to in edges.goes_to.iter() {
+                    predecessors[to.inner].push(BasicBlockId::new(from));
+                }
+            }
+
+            predecessors
+        });
+
+        &predecessors[id.inner]
+    }
+
+    pub fn basic_block_ids(&self) -> impl DoubleEndedIterator<Item = BasicBlockId> {
         (0..self.nodes.len()).map(BasicBlockId::new)
     }
 }
+
+/// Code with its own control flow, either the top level statements or a function body.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Procedure {
+    Main,
+    Function(FunctionId),
+}
+
+#[derive(Debug)]
+pub struct Program {
+    pub procedures: Vec<(Procedure, ControlFlowGraph)>,
+}
+
+impl Program {
+    pub fn get(&self, procedure: Procedure) -> Option<&ControlFlowGraph> {
+        self.procedures
+            .iter()
+            .find(|(candidate, _)| *candidate == procedure)
+            .map(|(_, cf_graph)| cf_graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predecessors_follow_edge_changes() {
+        let mut cf_graph = ControlFlowGraph::new();
+        let (entry, exit) = (cf_graph.entry_point(), cf_graph.exit_point());
+        let block = cf_graph.fresh_block();
+
+        cf_graph.add_edge(entry, EdgeKind::Unconditional, block);
+        cf_graph.add_edge(block, EdgeKind::Unconditional, exit);
+        assert_eq!(cf_graph.predecessors(block), [entry]);
+
+        let edges: &mut Edges = cf_graph.get_mut(entry);
+        edges.assign(0, exit);
+
+        assert_eq!(cf_graph.predecessors(block), []);
+        assert_eq!(cf_graph.predecessors(exit), [entry, block]);
+    }
+}
@@ -0,0 +1,141 @@
+//! A variable is live at some point when a path from there reads it
+//! before writing it again. Writes done while the variable is not
+//! live are dead stores, their value can never be observed.
+
+use std::collections::BTreeSet;
+
+use rlox_ast::Ast;
+use rlox_infra::StructVec;
+
+use crate::dataflow::{self, Analysis, Direction, Facts, Point};
+use crate::variables::{self, Access, Definition, VarId, Variables};
+use crate::{BasicBlockId, ControlFlowGraph};
+
+pub struct Liveness<'a> {
+    pub cf_graph: &'a ControlFlowGraph,
+    pub variables: &'a Variables,
+    pub ast: &'a Ast,
+}
+
+impl Analysis for Liveness<'_> {
+    type Fact = BTreeSet<VarId>;
+
+    const DIRECTION: Direction = Direction::Backward;
+
+    fn bottom(&self) -> Self::Fact {
+        BTreeSet::new()
+    }
+
+    fn boundary(&self) -> Self::Fact {
+        BTreeSet::new()
+    }
+
+    fn transfer(&self, id: BasicBlockId, input: &Self::Fact) -> Self::Fact {
+        let mut live = input.clone();
+
+        for access in variables::accesses(self.cf_graph.get(id), self.variables, self.ast)
+            .into_iter()
+            .rev()
+        {
+            match access {
+                Access::Read(_, var) => live.insert(var),
+                Access::Write(_, var) => live.remove(&var),
+            };
+        }
+
+        live
+    }
+}
+
+impl Liveness<'_> {
+    pub fn solve(&self) -> Facts<BTreeSet<VarId>> {
+        dataflow::solve(self, self.cf_graph)
+    }
+
+    /// Writes whose variable is not live right after them, in the order they appear in each block.
+    pub fn dead_stores(&self) -> Vec<(Definition, VarId)> {
+        let facts = self.solve();
+        let mut dead_stores = Vec::new();
+
+        for id in self.cf_graph.basic_block_ids() {
+            let mut live = facts.get(Point::Out(id)).clone();
+            let mut block_stores = Vec::new();
+
+            for access in variables::accesses(self.cf_graph.get(id), self.variables, self.ast)
+                .into_iter()
+                .rev()
+            {
+                match access {
+                    Access::Read(_, var) => {
+                        live.insert(var);
+                    }
+
+                    Access::Write(definition, var) => {
+                        if !live.remove(&var) {
+                            block_stores.push((definition, var));
+                        }
+                    }
+                }
+            }
+
+            dead_stores.extend(block_stores.into_iter().rev());
+        }
+
+        dead_stores
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use super::*;
+    use crate::build_cfg;
+
+    fn dead_stores(code: &str) -> Vec<String> {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        let cf_graph = build_cfg::from_sequence_of_stmts(ast.main(), &ast);
+        let variables = variables::resolve(&ast);
+        let liveness = Liveness {
+            cf_graph: &cf_graph,
+            variables: &variables,
+            ast: &ast,
+        };
+
+        liveness
+            .dead_stores()
+            .into_iter()
+            .map(|(definition, var)| {
+                let kind = match definition {
+                    Definition::Param(..) => "param",
+                    Definition::Declaration(_) => "declaration",
+                    Definition::Assign(_) => "assign",
+                };
+
+                format!("{kind} {}", variables.name(var, &ast))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn overwritten_values_are_dead() {
+        let found = dead_stores("var a = 1; a = 2; println(a); a = 3;");
+        assert_eq!(found, ["declaration a", "assign a"]);
+    }
+
+    #[test]
+    fn values_read_in_some_path_are_live() {
+        let found = dead_stores("var a = 1; var b = true; if b { println(a); } else { a = 2; } println(a);");
+        assert!(found.is_empty(), "{found:?}");
+    }
+
+    #[test]
+    fn values_read_by_the_next_iteration_are_live() {
+        let found = dead_stores("var a = 0; while a < 10 { a = a + 1; }");
+        assert!(found.is_empty(), "{found:?}");
+    }
+}
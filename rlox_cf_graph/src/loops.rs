@@ -0,0 +1,243 @@
+//! Natural loops. An edge is a back edge when its target dominates its source,
+//! the loop of a back edge is its target, the header, plus every block that
+//! reaches the source without going through the header. Back edges sharing a
+//! header form a single loop, and loops nest following their bodies.
+
+use crate::dominators::DominatorTree;
+use crate::{BasicBlockId, CfgVec, ControlFlowGraph};
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LoopId {
+    inner: usize,
+}
+
+impl LoopId {
+    pub fn index(&self) -> usize {
+        self.inner
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Loop {
+    pub header: BasicBlockId,
+    /// Sources of the back edges going to the header.
+    pub latches: Vec<BasicBlockId>,
+    /// Every block of the loop, including the header and the blocks of nested loops.
+    pub body: Vec<BasicBlockId>,
+    /// Edges leaving the loop, as pairs of a block in the body and a block outside of it.
+    pub exits: Vec<(BasicBlockId, BasicBlockId)>,
+    pub parent: Option<LoopId>,
+    /// Outermost loops have depth one.
+    pub depth: usize,
+}
+
+impl Loop {
+    pub fn contains(&self, id: BasicBlockId) -> bool {
+        self.body.binary_search(&id).is_ok()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoopForest {
+    loops: Vec<Loop>,
+    innermost: CfgVec<Option<LoopId>>,
+}
+
+impl LoopForest {
+    /// Loops are ordered by the id of their header.
+    pub fn loops(&self) -> impl Iterator<Item = (LoopId, &Loop)> {
+        self.loops.iter().enumerate().map(|(inner, lp)| {
+            let id = LoopId {
+                inner,
+            };
+
+            (id, lp)
+        })
+    }
+
+    pub fn get(&self, id: LoopId) -> &Loop {
+        &self.loops[id.inner]
+    }
+
+    pub fn innermost(&self, id: BasicBlockId) -> Option<LoopId> {
+        self.innermost[id]
+    }
+
+    /// Number of loops containing the block.
+    pub fn depth(&self, id: BasicBlockId) -> usize {
+        self.innermost[id].map_or(0, |lp| self.get(lp).depth)
+    }
+
+    pub fn is_header(&self, id: BasicBlockId) -> bool {
+        self.innermost[id].is_some_and(|lp| self.get(lp).header == id)
+    }
+
+    pub fn is_back_edge(&self, from: BasicBlockId, to: BasicBlockId) -> bool {
+        self.innermost[to].is_some_and(|lp| self.get(lp).header == to && self.get(lp).latches.contains(&from))
+    }
+}
+
+pub fn find(cf_graph: &ControlFlowGraph, dominators: &DominatorTree) -> LoopForest {
+    let mut loops: Vec<Loop> = Vec::new();
+
+    for header in cf_graph.basic_block_ids() {
+        let latches: Vec<_> = cf_graph
+            .predecessors(header)
+            .iter()
+            .copied()
+            .filter(|latch| dominators.is_reachable(*latch) && dominators.dominates(header, *latch))
+            .collect();
+
+        if latches.is_empty() {
+            continue;
+        }
+
+        let body = loop_body(header, &latches, cf_graph);
+
+        let mut exits = Vec::new();
+        for id in body.iter().copied() {
+            for successor in cf_graph.successors(id).iter().copied() {
+                if body.binary_search(&successor).is_err() {
+                    exits.push((id, successor));
+                }
+            }
+        }
+
+        loops.push(Loop {
+            header,
+            latches,
+            body,
+            exits,
+            parent: None,
+            depth: 1,
+        });
+    }
+
+    // Loop bodies are either nested or disjoint. The parent of a loop is the
+    // smallest other loop containing its header.
+    for index in 0..loops.len() {
+        let header = loops[index].header;
+
+        let parent = (0..loops.len())
+            .filter(|candidate| *candidate != index && loops[*candidate].contains(header))
+            .min_by_key(|candidate| loops[*candidate].body.len());
+
+        loops[index].parent = parent.map(|inner| LoopId {
+            inner,
+        });
+    }
+
+    for index in 0..loops.len() {
+        let mut ancestor = loops[index].parent;
+
+        while let Some(parent) = ancestor {
+            loops[index].depth += 1;
+            ancestor = loops[parent.inner].parent;
+        }
+    }
+
+    let mut innermost: CfgVec<Option<LoopId>> = CfgVec::filled(None, cf_graph);
+    for (index, lp) in loops.iter().enumerate() {
+        for id in lp.body.iter().copied() {
+            let deeper = innermost[id].is_none_or(|current: LoopId| loops[current.inner].depth < lp.depth);
+
+            if deeper {
+                innermost[id] = Some(LoopId {
+                    inner: index,
+                });
+            }
+        }
+    }
+
+    LoopForest {
+        loops,
+        innermost,
+    }
+}
+
+/// Walks the graph backwards from the latches, the header stops the walk.
+fn loop_body(header: BasicBlockId, latches: &[BasicBlockId], cf_graph: &ControlFlowGraph) -> Vec<BasicBlockId> {
+    let mut in_body = CfgVec::filled(false, cf_graph);
+    in_body[header] = true;
+
+    let mut stack = Vec::new();
+    for latch in latches.iter().copied() {
+        if !in_body[latch] {
+            in_body[latch] = true;
+            stack.push(latch);
+        }
+    }
+
+    while let Some(id) = stack.pop() {
+        for predecessor in cf_graph.predecessors(id).iter().copied() {
+            if !in_body[predecessor] {
+                in_body[predecessor] = true;
+                stack.push(predecessor);
+            }
+        }
+    }
+
+    cf_graph
+        .basic_block_ids()
+        .filter(|id| in_body[*id])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use super::*;
+    use crate::{build_cfg, dominators};
+
+    fn build(code: &str) -> ControlFlowGraph {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        build_cfg::from_sequence_of_stmts(ast.main(), &ast)
+    }
+
+    #[test]
+    fn straight_line_code_has_no_loops() {
+        let cf_graph = build("var a = 1; if a == 1 { a = 2; }");
+        let forest = find(&cf_graph, &dominators::dominators(&cf_graph));
+
+        assert_eq!(forest.loops().count(), 0);
+    }
+
+    #[test]
+    fn nested_loops() {
+        // 0: entry, 2: outer header, 3: outer body, 4: inner header, 5: inner body, 6: after the inner loop, 1: exit.
+        let cf_graph = build("var a = 0; while a < 3 { var b = 0; while b < 3 { b = b + 1; } a = a + 1; }");
+        let ids: Vec<_> = cf_graph.basic_block_ids().collect();
+        let [entry, exit, outer_header, outer_body, inner_header, inner_body, after_inner] = ids[..] else {
+            panic!("unexpected number of blocks");
+        };
+
+        let forest = find(&cf_graph, &dominators::dominators(&cf_graph));
+        let [(outer_id, outer), (inner_id, inner)] = forest.loops().collect::<Vec<_>>()[..] else {
+            panic!("expected two loops");
+        };
+
+        assert_eq!(outer.header, outer_header);
+        assert_eq!(outer.latches, [after_inner]);
+        assert_eq!(outer.body, [outer_header, outer_body, inner_header, inner_body, after_inner]);
+        assert_eq!(outer.exits, [(outer_header, exit)]);
+        assert_eq!(outer.parent, None);
+
+        assert_eq!(inner.header, inner_header);
+        assert_eq!(inner.body, [inner_header, inner_body]);
+        assert_eq!(inner.exits, [(inner_header, after_inner)]);
+        assert_eq!(inner.parent, Some(outer_id));
+
+        assert_eq!(forest.depth(entry), 0);
+        assert_eq!(forest.depth(outer_body), 1);
+        assert_eq!(forest.depth(inner_body), 2);
+        assert_eq!(forest.innermost(inner_header), Some(inner_id));
+        assert!(forest.is_header(inner_header));
+        assert!(forest.is_back_edge(inner_body, inner_header));
+        assert!(!forest.is_back_edge(outer_body, inner_header));
+    }
+}
@@ -0,0 +1,89 @@
+use rlox_ast::expr::Expr;
+use rlox_ast::stmt::StmtNode;
+use rlox_infra::StructVec;
+
+use crate::{BasicBlock, BasicBlockId, CfgVec, ControlFlowGraph, EdgeKind, Edges, Terminator};
+
+/// Blocks that can run starting from the entry point. `condition` gives the value of the
+/// branch conditions known before running the program, only their taken edge is followed.
+pub fn reachable<F>(cf_graph: &ControlFlowGraph, condition: F) -> CfgVec<bool>
+where
+    F: Fn(StmtNode<Expr>) -> Option<bool>,
+{
+    let mut reachable = CfgVec::filled(false, cf_graph);
+    let mut stack = vec![cf_graph.entry_point()];
+    reachable[cf_graph.entry_point()] = true;
+
+    while let Some(id) = stack.pop() {
+        let basic_block: &BasicBlock = cf_graph.get(id);
+        let known = match basic_block.terminator {
+            Terminator::Branch(node) => condition(node),
+            Terminator::Jump | Terminator::Exit => None,
+        };
+
+        let edges: &Edges = cf_graph.get(id);
+
+        for index in 0..edges.len() {
+            let taken = match (known, *edges.get(index)) {
+                (Some(value), EdgeKind::True) => value,
+                (Some(value), EdgeKind::False) => !value,
+                _ => true,
+            };
+
+            let target: BasicBlockId = *edges.get(index);
+
+            if taken && !reachable[target] {
+                reachable[target] = true;
+                stack.push(target);
+            }
+        }
+    }
+
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_ast::expr::ExprKind;
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use super::*;
+    use crate::build_cfg;
+
+    fn unreachable(code: &str) -> Vec<BasicBlockId> {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        let cf_graph = build_cfg::from_sequence_of_stmts(ast.main(), &ast);
+        let reachable = reachable(&cf_graph, |node| match node.inner.kind() {
+            ExprKind::Boolean(value) => Some(value),
+            _ => None,
+        });
+
+        cf_graph
+            .basic_block_ids()
+            .filter(|id| !reachable[*id])
+            .collect()
+    }
+
+    #[test]
+    fn every_block_is_reachable_without_constant_conditions() {
+        assert!(unreachable("var a = 1; if a == 1 { a = 2; } while a < 10 { a = a + 1; }").is_empty());
+    }
+
+    #[test]
+    fn constant_branches_only_take_one_edge() {
+        // 0: branch, 2: if, 3: else, 4: join, 1: exit.
+        let found = unreachable("var a = 1; if false { a = 2; } else { a = 3; } println(a);");
+        assert_eq!(found, [BasicBlockId::new(2)]);
+    }
+
+    #[test]
+    fn infinite_loops_never_exit() {
+        // 0: entry, 2: header, 3: body, 4: after the loop, 1: exit.
+        let found = unreachable("var a = 1; while true { a = a + 1; } println(a);");
+        assert_eq!(found, [BasicBlockId::new(1), BasicBlockId::new(4)]);
+    }
+}
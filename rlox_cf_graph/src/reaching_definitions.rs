@@ -0,0 +1,151 @@
+//! A definition reaches some point when there is a path from the
+//! definition to that point that does not write the variable again.
+
+use std::collections::BTreeSet;
+
+use rlox_ast::Ast;
+use rlox_ast::expr::ExprId;
+use rlox_infra::StructVec;
+
+use crate::dataflow::{self, Analysis, Direction, Point};
+use crate::variables::{self, Access, Definition, VarId, Variables};
+use crate::{BasicBlockId, ControlFlowGraph, Procedure};
+
+pub struct ReachingDefinitions<'a> {
+    pub procedure: Procedure,
+    pub cf_graph: &'a ControlFlowGraph,
+    pub variables: &'a Variables,
+    pub ast: &'a Ast,
+}
+
+/// A read of a variable and every definition that may have written the value it gets.
+#[derive(Debug, Clone)]
+pub struct Use {
+    pub expr: ExprId,
+    pub var: VarId,
+    pub definitions: Vec<Definition>,
+}
+
+impl Analysis for ReachingDefinitions<'_> {
+    type Fact = BTreeSet<(VarId, Definition)>;
+
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn bottom(&self) -> Self::Fact {
+        BTreeSet::new()
+    }
+
+    /// Parameters are defined by the caller before the body runs.
+    fn boundary(&self) -> Self::Fact {
+        let Procedure::Function(function) = self.procedure else {
+            return BTreeSet::new();
+        };
+
+        self.variables
+            .params(function)
+            .iter()
+            .copied()
+            .enumerate()
+            .map(|(index, var)| (var, Definition::Param(function, index)))
+            .collect()
+    }
+
+    fn transfer(&self, id: BasicBlockId, input: &Self::Fact) -> Self::Fact {
+        let mut reaching = input.clone();
+
+        for access in variables::accesses(self.cf_graph.get(id), self.variables, self.ast) {
+            if let Access::Write(definition, var) = access {
+                kill_and_generate(&mut reaching, definition, var);
+            }
+        }
+
+        reaching
+    }
+}
+
+impl ReachingDefinitions<'_> {
+    /// Every read of the procedure, in the order they appear in each block.
+    pub fn uses(&self) -> Vec<Use> {
+        let facts = dataflow::solve(self, self.cf_graph);
+        let mut uses = Vec::new();
+
+        for id in self.cf_graph.basic_block_ids() {
+            let mut reaching = facts.get(Point::In(id)).clone();
+
+            for access in variables::accesses(self.cf_graph.get(id), self.variables, self.ast) {
+                match access {
+                    Access::Read(expr, var) => uses.push(Use {
+                        expr,
+                        var,
+                        definitions: reaching
+                            .iter()
+                            .filter(|(reaching_var, _)| *reaching_var == var)
+                            .map(|(_, definition)| *definition)
+                            .collect(),
+                    }),
+
+                    Access::Write(definition, var) => kill_and_generate(&mut reaching, definition, var),
+                }
+            }
+        }
+
+        uses
+    }
+}
+
+fn kill_and_generate(reaching: &mut BTreeSet<(VarId, Definition)>, definition: Definition, var: VarId) {
+    reaching.retain(|(reaching_var, _)| *reaching_var != var);
+    reaching.insert((var, definition));
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use super::*;
+    use crate::build_cfg;
+
+    fn uses(code: &str) -> Vec<(String, Vec<Definition>)> {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        let cf_graph = build_cfg::from_sequence_of_stmts(ast.main(), &ast);
+        let variables = variables::resolve(&ast);
+        let analysis = ReachingDefinitions {
+            procedure: Procedure::Main,
+            cf_graph: &cf_graph,
+            variables: &variables,
+            ast: &ast,
+        };
+
+        analysis
+            .uses()
+            .into_iter()
+            .map(|read| (variables.name(read.var, &ast).to_string(), read.definitions))
+            .collect()
+    }
+
+    #[test]
+    fn writes_kill_previous_definitions() {
+        let found = uses("var a = 1; a = 2; println(a);");
+        let [(name, definitions)] = &found[..] else {
+            panic!("unexpected uses {found:?}");
+        };
+
+        assert_eq!(name, "a");
+        assert!(matches!(definitions[..], [Definition::Assign(_)]));
+    }
+
+    #[test]
+    fn definitions_of_both_branches_reach_the_join() {
+        let found = uses("var a; var b = true; if b { a = 1; } println(a);");
+        let [_, (name, definitions)] = &found[..] else {
+            panic!("unexpected uses {found:?}");
+        };
+
+        assert_eq!(name, "a");
+        assert!(matches!(definitions[..], [Definition::Declaration(_), Definition::Assign(_)]));
+    }
+}
@@ -0,0 +1,483 @@
+//! Semi-pruned SSA form: phi nodes are only placed for variables that are read
+//! in a different block than the one writing them, following the dominance
+//! frontiers of their definitions. The statements are not rewritten, instead
+//! every definition and read of a variable is mapped to its version.
+
+use std::collections::{HashMap, HashSet};
+
+use rlox_ast::Ast;
+use rlox_ast::expr::{Expr, ExprId, ExprKind};
+use rlox_infra::StructVec;
+
+use crate::dominators::{self, DominatorTree};
+use crate::variables::{self, Access, Definition, VarId, Variables};
+use crate::{BasicBlock, BasicBlockId, BasicBlockValue, CfgVec, ControlFlowGraph, Procedure, Terminator};
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SsaVar {
+    pub var: VarId,
+    pub version: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Phi {
+    pub target: SsaVar,
+    // One operand per predecessor of the block.
+    pub operands: Vec<(BasicBlockId, SsaVar)>,
+}
+
+#[derive(Debug)]
+pub struct Ssa {
+    phis: CfgVec<Vec<Phi>>,
+    definitions: HashMap<Definition, SsaVar>,
+    reads: HashMap<ExprId, SsaVar>,
+}
+
+impl Ssa {
+    pub fn phis(&self, id: BasicBlockId) -> &[Phi] {
+        &self.phis[id]
+    }
+
+    pub fn definition(&self, definition: Definition) -> Option<SsaVar> {
+        self.definitions.get(&definition).copied()
+    }
+
+    /// Version read by an identifier expression.
+    pub fn read(&self, expr: ExprId) -> Option<SsaVar> {
+        self.reads.get(&expr).copied()
+    }
+
+    pub fn display<'a>(&'a self, ctxt: Ctxt<'a>) -> SsaDisplay<'a> {
+        SsaDisplay {
+            ssa: self,
+            ctxt,
+        }
+    }
+
+    /// Phi nodes, statements and terminator of the block, one per line.
+    pub fn lines(&self, id: BasicBlockId, ctxt: Ctxt) -> Vec<String> {
+        let basic_block: &BasicBlock = ctxt.cf_graph.get(id);
+        let mut lines = Vec::new();
+
+        for phi in self.phis[id].iter() {
+            let operands: Vec<_> = phi
+                .operands
+                .iter()
+                .map(|(pred, operand)| format!("{pred}: {}", self.ssa_var(*operand, ctxt)))
+                .collect();
+
+            lines.push(format!("{} = phi({})", self.ssa_var(phi.target, ctxt), operands.join(", ")));
+        }
+
+        for stmt in basic_block.stmts.iter() {
+            match stmt {
+                BasicBlockValue::Declaration(node) => {
+                    let target = match self.definition(Definition::Declaration(node.stmt_id)) {
+                        Some(target) => self.ssa_var(target, ctxt),
+                        None => ctxt.ast[ctxt.ast[node.inner].identifier].to_string(),
+                    };
+
+                    match ctxt.ast[node.inner].value {
+                        None => lines.push(format!("var {target}")),
+                        Some(value) => lines.push(format!("var {target} = {}", self.expr(value, ctxt))),
+                    }
+                }
+
+                BasicBlockValue::StmtExpr(node) => lines.push(self.expr(node.inner, ctxt)),
+
+                BasicBlockValue::EnterBlock(_) | BasicBlockValue::LeaveBlock(_) => (),
+            }
+        }
+
+        let successors: Vec<_> = ctxt
+            .cf_graph
+            .successors(id)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        match basic_block.terminator {
+            Terminator::Branch(node) => {
+                lines.push(format!("branch {} -> {}", self.expr(node.inner, ctxt), successors.join(", ")))
+            }
+            Terminator::Jump => lines.push(format!("jump {}", successors.join(", "))),
+            Terminator::Exit => lines.push("exit".to_string()),
+        }
+
+        lines
+    }
+
+    /// Variables sharing their name with another one are told apart by their id.
+    fn ssa_var(&self, ssa_var: SsaVar, ctxt: Ctxt) -> String {
+        let variables = ctxt.variables;
+        let name = variables.name(ssa_var.var, ctxt.ast);
+
+        let shadows = variables
+            .ids()
+            .any(|other| other != ssa_var.var && variables.name(other, ctxt.ast) == name);
+
+        match shadows {
+            true => format!("{name}#{}.{}", ssa_var.var.index(), ssa_var.version),
+            false => format!("{name}.{}", ssa_var.version),
+        }
+    }
+
+    fn expr(&self, expr: Expr, ctxt: Ctxt) -> String {
+        let ast = ctxt.ast;
+
+        match expr.kind() {
+            ExprKind::Identifier(identifier) => match self.read(expr.global_id()) {
+                Some(ssa_var) => self.ssa_var(ssa_var, ctxt),
+                None => ast[identifier].to_string(),
+            },
+
+            ExprKind::Assign(inner) => {
+                let lhs = match self.definition(Definition::Assign(expr.global_id())) {
+                    Some(ssa_var) => self.ssa_var(ssa_var, ctxt),
+                    None => self.expr(ast[inner].lhs, ctxt),
+                };
+
+                format!("{lhs} = {}", self.expr(ast[inner].rhs, ctxt))
+            }
+
+            ExprKind::Binary(inner) => {
+                let binary = &ast[inner];
+                format!("{} {} {}", self.operand(binary.lhs, ctxt), binary.operator, self.operand(binary.rhs, ctxt))
+            }
+
+            ExprKind::Unary(inner) => format!("{}{}", ast[inner].operator, self.operand(ast[inner].operand, ctxt)),
+
+            ExprKind::Call(inner) => {
+                let arguments: Vec<_> = ast[inner]
+                    .arguments
+                    .iter()
+                    .map(|argument| self.expr(*argument, ctxt))
+                    .collect();
+
+                format!("{}({})", self.operand(ast[inner].lhs, ctxt), arguments.join(", "))
+            }
+
+            ExprKind::String(inner) => format!("{:?}", &ast[inner]),
+            ExprKind::Natural(inner) => inner.to_string(),
+            ExprKind::Decimal(inner) => inner.to_string(),
+            ExprKind::Boolean(inner) => inner.to_string(),
+            ExprKind::Nil => "nil".to_string(),
+        }
+    }
+
+    /// Nested operations are wrapped in parentheses, so precedence is explicit.
+    fn operand(&self, expr: Expr, ctxt: Ctxt) -> String {
+        match expr.kind() {
+            ExprKind::Binary(_) | ExprKind::Assign(_) => format!("({})", self.expr(expr, ctxt)),
+            _ => self.expr(expr, ctxt),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Ctxt<'a> {
+    pub cf_graph: &'a ControlFlowGraph,
+    pub variables: &'a Variables,
+    pub ast: &'a Ast,
+}
+
+pub struct SsaDisplay<'a> {
+    ssa: &'a Ssa,
+    ctxt: Ctxt<'a>,
+}
+
+impl std::fmt::Display for SsaDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for id in self.ctxt.cf_graph.basic_block_ids() {
+            writeln!(f, "{id}:")?;
+
+            for line in self.ssa.lines(id, self.ctxt) {
+                writeln!(f, "    {line}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct State<'a> {
+    ctxt: Ctxt<'a>,
+    tree: DominatorTree,
+    accesses: CfgVec<Vec<Access>>,
+    phis: PendingPhis,
+    versions: HashMap<VarId, usize>,
+    stacks: HashMap<VarId, Vec<SsaVar>>,
+    definitions: HashMap<Definition, SsaVar>,
+    reads: HashMap<ExprId, SsaVar>,
+}
+
+impl State<'_> {
+    fn fresh(&mut self, var: VarId) -> SsaVar {
+        let version = self.versions.entry(var).or_default();
+        let ssa_var = SsaVar {
+            var,
+            version: *version,
+        };
+
+        *version += 1;
+        self.stacks.entry(var).or_default().push(ssa_var);
+
+        ssa_var
+    }
+
+    fn current(&self, var: VarId) -> Option<SsaVar> {
+        self.stacks
+            .get(&var)
+            .and_then(|stack| stack.last().copied())
+    }
+}
+
+pub fn build(procedure: Procedure, ctxt: Ctxt) -> Ssa {
+    let cf_graph = ctxt.cf_graph;
+
+    let mut accesses = CfgVec::filled(Vec::new(), cf_graph);
+    for id in cf_graph.basic_block_ids() {
+        accesses[id] = variables::accesses(cf_graph.get(id), ctxt.variables, ctxt.ast);
+    }
+
+    // Parameters are defined before the entry point runs.
+    let params: Vec<_> = match procedure {
+        Procedure::Main => vec![],
+        Procedure::Function(function) => ctxt
+            .variables
+            .params(function)
+            .iter()
+            .enumerate()
+            .map(|(index, var)| Access::Write(Definition::Param(function, index), *var))
+            .collect(),
+    };
+
+    let entry_point = cf_graph.entry_point();
+    accesses[entry_point].splice(0..0, params);
+
+    let tree = dominators::dominators(cf_graph);
+    let phis = place_phis(&tree, &accesses, cf_graph);
+
+    let mut state = State {
+        ctxt,
+        tree,
+        accesses,
+        phis,
+        versions: HashMap::new(),
+        stacks: HashMap::new(),
+        definitions: HashMap::new(),
+        reads: HashMap::new(),
+    };
+
+    rename(entry_point, &mut state);
+
+    // A missing operand means the variable is out of scope in the block,
+    // so the phi node can not be read and is dropped.
+    let mut phis = CfgVec::filled(Vec::new(), cf_graph);
+
+    for id in cf_graph.basic_block_ids() {
+        for (_, target, operands) in std::mem::take(&mut state.phis[id]) {
+            let operands: Option<Vec<_>> = operands
+                .into_iter()
+                .map(|(pred, operand)| operand.map(|operand| (pred, operand)))
+                .collect();
+
+            if let (Some(target), Some(operands)) = (target, operands) {
+                phis[id].push(Phi {
+                    target,
+                    operands,
+                });
+            }
+        }
+    }
+
+    Ssa {
+        phis,
+        definitions: state.definitions,
+        reads: state.reads,
+    }
+}
+
+/// Phi nodes being built, the target is set once the block is renamed and operands
+/// are missing when the variable is not declared in every predecessor.
+type PendingPhis = CfgVec<Vec<(VarId, Option<SsaVar>, Vec<(BasicBlockId, Option<SsaVar>)>)>>;
+
+fn place_phis(tree: &DominatorTree, accesses: &CfgVec<Vec<Access>>, cf_graph: &ControlFlowGraph) -> PendingPhis {
+    let frontiers = tree.frontiers(cf_graph);
+
+    // Variables read before being written in the same block, the only ones that need phi nodes.
+    let mut non_local = HashSet::new();
+    let mut def_blocks: HashMap<VarId, Vec<BasicBlockId>> = HashMap::new();
+
+    for id in cf_graph.basic_block_ids() {
+        let mut written = HashSet::new();
+
+        for access in accesses[id].iter() {
+            match *access {
+                Access::Read(_, var) if !written.contains(&var) => {
+                    non_local.insert(var);
+                }
+                Access::Read(..) => (),
+                Access::Write(_, var) => {
+                    written.insert(var);
+                    def_blocks.entry(var).or_default().push(id);
+                }
+            }
+        }
+    }
+
+    let mut phis = CfgVec::filled(Vec::new(), cf_graph);
+
+    let mut vars: Vec<_> = non_local.into_iter().collect();
+    vars.sort();
+
+    for var in vars {
+        let mut worklist = def_blocks.remove(&var).unwrap_or_default();
+        let mut has_phi = HashSet::new();
+
+        while let Some(id) = worklist.pop() {
+            for frontier in frontiers.get(id).iter().copied() {
+                if !has_phi.insert(frontier) {
+                    continue;
+                }
+
+                let operands = cf_graph
+                    .predecessors(frontier)
+                    .iter()
+                    .map(|pred| (*pred, None))
+                    .collect();
+
+                phis[frontier].push((var, None, operands));
+                worklist.push(frontier);
+            }
+        }
+    }
+
+    phis
+}
+
+fn rename(id: BasicBlockId, state: &mut State) {
+    let mut pushed = Vec::new();
+
+    for index in 0..state.phis[id].len() {
+        let var = state.phis[id][index].0;
+        state.phis[id][index].1 = Some(state.fresh(var));
+        pushed.push(var);
+    }
+
+    for access in std::mem::take(&mut state.accesses[id]) {
+        match access {
+            Access::Read(expr, var) => {
+                if let Some(current) = state.current(var) {
+                    state.reads.insert(expr, current);
+                }
+            }
+
+            Access::Write(definition, var) => {
+                let ssa_var = state.fresh(var);
+                state.definitions.insert(definition, ssa_var);
+                pushed.push(var);
+            }
+        }
+    }
+
+    for successor in state.ctxt.cf_graph.successors(id).iter().copied() {
+        for index in 0..state.phis[successor].len() {
+            let var = state.phis[successor][index].0;
+            let current = state.current(var);
+
+            // Every edge has its own operand, even when two edges come from the same block.
+            let operands = &mut state.phis[successor][index].2;
+            if let Some(operand) = operands
+                .iter_mut()
+                .find(|(pred, operand)| *pred == id && operand.is_none())
+            {
+                operand.1 = current;
+            }
+        }
+    }
+
+    for child in state.tree.children(id).to_vec() {
+        rename(child, state);
+    }
+
+    for var in pushed {
+        if let Some(stack) = state.stacks.get_mut(&var) {
+            stack.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use super::*;
+    use crate::build_cfg;
+
+    fn ssa_text(code: &str) -> String {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        let cf_graph = build_cfg::from_sequence_of_stmts(ast.main(), &ast);
+        let variables = variables::resolve(&ast);
+        let ctxt = Ctxt {
+            cf_graph: &cf_graph,
+            variables: &variables,
+            ast: &ast,
+        };
+
+        build(Procedure::Main, ctxt).display(ctxt).to_string()
+    }
+
+    #[test]
+    fn loop_header_merges_versions() {
+        let found = ssa_text("var a = 0; while a < 10 { var b = a; a = b + 1; } println(a);");
+        let expected = "\
+BBId(0):
+    var a.0 = 0
+    jump BBId(2)
+BBId(1):
+    exit
+BBId(2):
+    a.1 = phi(BBId(0): a.0, BBId(3): a.2)
+    branch a.1 < 10 -> BBId(3), BBId(4)
+BBId(3):
+    var b.0 = a.1
+    a.2 = b.0 + 1
+    jump BBId(2)
+BBId(4):
+    println(a.1)
+    jump BBId(1)
+";
+
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn shadowing_declares_new_variables() {
+        let found = ssa_text("var a = 1; if a == 1 { var a = 2; a = 3; } else { a = 4; } println(a);");
+        let expected = "\
+BBId(0):
+    var a#0.0 = 1
+    branch a#0.0 == 1 -> BBId(2), BBId(3)
+BBId(1):
+    exit
+BBId(2):
+    var a#1.0 = 2
+    a#1.1 = 3
+    jump BBId(4)
+BBId(3):
+    a#0.1 = 4
+    jump BBId(4)
+BBId(4):
+    a#0.2 = phi(BBId(2): a#0.0, BBId(3): a#0.1)
+    println(a#0.2)
+    jump BBId(1)
+";
+
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use rlox_ast::expr::{Expr, ExprId, ExprKind};
+use rlox_ast::stmt::{DeclarationId, Stmt, StmtId, StmtKind, StmtNode, stmt_node};
+use rlox_ast::{Ast, FunctionId, Identifier};
+
+use crate::{BasicBlock, BasicBlockValue, Terminator};
+
+/// A variable after scope resolution, two declarations with the
+/// same name are always different variables.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VarId {
+    inner: usize,
+}
+
+impl VarId {
+    pub fn index(&self) -> usize {
+        self.inner
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Declarator {
+    Declaration(StmtNode<DeclarationId>),
+    Param(FunctionId, Identifier),
+}
+
+#[derive(Debug, Default)]
+pub struct Variables {
+    declarators: Vec<Declarator>,
+    declarations: HashMap<StmtId, VarId>,
+    params: HashMap<FunctionId, Vec<VarId>>,
+    // Identifier expressions, including the left side of assignments.
+    uses: HashMap<ExprId, VarId>,
+}
+
+impl Variables {
+    pub fn len(&self) -> usize {
+        self.declarators.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.declarators.is_empty()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = VarId> {
+        (0..self.declarators.len()).map(|inner| VarId {
+            inner,
+        })
+    }
+
+    pub fn declarator(&self, var: VarId) -> Declarator {
+        self.declarators[var.inner]
+    }
+
+    pub fn name<'a>(&self, var: VarId, ast: &'a Ast) -> &'a str {
+        match self.declarators[var.inner] {
+            Declarator::Param(_, identifier) => &ast[identifier],
+            Declarator::Declaration(node) => &ast[ast[node.inner].identifier],
+        }
+    }
+
+    pub fn declaration(&self, stmt: StmtId) -> Option<VarId> {
+        self.declarations.get(&stmt).copied()
+    }
+
+    pub fn params(&self, function: FunctionId) -> &[VarId] {
+        self.params.get(&function).map_or(&[], Vec::as_slice)
+    }
+
+    /// Variable referenced by an identifier expression, if it was declared.
+    pub fn variable(&self, expr: ExprId) -> Option<VarId> {
+        self.uses.get(&expr).copied()
+    }
+}
+
+struct State<'a> {
+    ast: &'a Ast,
+    variables: Variables,
+    scopes: Vec<Vec<(&'a str, VarId)>>,
+}
+
+impl<'a> State<'a> {
+    fn declare(&mut self, name: &'a str, declarator: Declarator) -> VarId {
+        let var = VarId {
+            inner: self.variables.declarators.len(),
+        };
+
+        self.variables.declarators.push(declarator);
+
+        // There is always at least one scope while resolving.
+        let scope = self.scopes.last_mut().expect("resolving outside a scope");
+        scope.push((name, var));
+
+        var
+    }
+
+    fn lookup(&self, name: &str) -> Option<VarId> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(candidate, _)| *candidate == name)
+            .map(|(_, var)| *var)
+    }
+}
+
+/// Resolves the top level statements and every function body, each one in its own scope.
+pub fn resolve(ast: &Ast) -> Variables {
+    let mut state = State {
+        ast,
+        variables: Variables::default(),
+        scopes: vec![vec![]],
+    };
+
+    for stmt in ast.main().iter().copied() {
+        resolve_stmt(stmt, &mut state);
+    }
+
+    for function in ast.functions() {
+        state.scopes = vec![vec![]];
+
+        let params: Vec<_> = ast[function]
+            .params
+            .iter()
+            .map(|param| state.declare(&ast[*param], Declarator::Param(function, *param)))
+            .collect();
+
+        state.variables.params.insert(function, params);
+
+        for stmt in ast[ast[function].body].iter().copied() {
+            resolve_stmt(stmt, &mut state);
+        }
+    }
+
+    state.variables
+}
+
+fn resolve_stmt(stmt: Stmt, state: &mut State) {
+    let ast = state.ast;
+
+    match stmt.kind() {
+        StmtKind::Expr(expr) => resolve_expr(expr, state),
+
+        StmtKind::Declaration(inner) => {
+            let declaration = &ast[inner];
+
+            // The value is evaluated before the variable exists.
+            if let Some(value) = declaration.value {
+                resolve_expr(value, state);
+            }
+
+            let var = state.declare(&ast[declaration.identifier], Declarator::Declaration(stmt_node!(stmt, inner)));
+            state.variables.declarations.insert(stmt.global_id(), var);
+        }
+
+        StmtKind::Block(inner) => {
+            state.scopes.push(vec![]);
+
+            for stmt in ast[inner].iter().copied() {
+                resolve_stmt(stmt, state);
+            }
+
+            state.scopes.pop();
+        }
+
+        StmtKind::IfElse(inner) => {
+            let if_else = &ast[inner];
+
+            resolve_expr(if_else.condition, state);
+            resolve_stmt(if_else.if_branch, state);
+
+            if let Some(else_branch) = if_else.else_branch {
+                resolve_stmt(else_branch, state);
+            }
+        }
+
+        StmtKind::While(inner) => {
+            let while_stmt = &ast[inner];
+
+            resolve_expr(while_stmt.condition, state);
+            resolve_stmt(while_stmt.body, state);
+        }
+    }
+}
+
+fn resolve_expr(expr: Expr, state: &mut State) {
+    let ast = state.ast;
+
+    match expr.kind() {
+        ExprKind::Identifier(identifier) => {
+            if let Some(var) = state.lookup(&ast[identifier]) {
+                state.variables.uses.insert(expr.global_id(), var);
+            }
+        }
+
+        ExprKind::Assign(inner) => {
+            resolve_expr(ast[inner].rhs, state);
+            resolve_expr(ast[inner].lhs, state);
+        }
+
+        ExprKind::Binary(inner) => {
+            resolve_expr(ast[inner].lhs, state);
+            resolve_expr(ast[inner].rhs, state);
+        }
+
+        ExprKind::Unary(inner) => resolve_expr(ast[inner].operand, state),
+
+        ExprKind::Call(inner) => {
+            resolve_expr(ast[inner].lhs, state);
+
+            for argument in ast[inner].arguments.iter().copied() {
+                resolve_expr(argument, state);
+            }
+        }
+
+        ExprKind::String(_) | ExprKind::Natural(_) | ExprKind::Decimal(_) | ExprKind::Boolean(_) | ExprKind::Nil => (),
+    }
+}
+
+/// Code that writes a variable.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Definition {
+    Param(FunctionId, usize),
+    Declaration(StmtId),
+    Assign(ExprId),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Access {
+    Read(ExprId, VarId),
+    Write(Definition, VarId),
+}
+
+/// Reads and writes of resolved variables in the order they happen when the block runs,
+/// including the condition of its terminator. Undeclared variables are ignored.
+pub fn accesses(basic_block: &BasicBlock, variables: &Variables, ast: &Ast) -> Vec<Access> {
+    let mut accesses = Vec::new();
+
+    for stmt in basic_block.stmts.iter() {
+        match stmt {
+            BasicBlockValue::Declaration(node) => {
+                if let Some(value) = ast[node.inner].value {
+                    expr_accesses(value, variables, ast, &mut accesses);
+                }
+
+                if let Some(var) = variables.declaration(node.stmt_id) {
+                    accesses.push(Access::Write(Definition::Declaration(node.stmt_id), var));
+                }
+            }
+
+            BasicBlockValue::StmtExpr(node) => expr_accesses(node.inner, variables, ast, &mut accesses),
+
+            BasicBlockValue::EnterBlock(_) | BasicBlockValue::LeaveBlock(_) => (),
+        }
+    }
+
+    if let Terminator::Branch(node) = basic_block.terminator {
+        expr_accesses(node.inner, variables, ast, &mut accesses);
+    }
+
+    accesses
+}
+
+fn expr_accesses(expr: Expr, variables: &Variables, ast: &Ast, accesses: &mut Vec<Access>) {
+    match expr.kind() {
+        ExprKind::Identifier(_) => {
+            if let Some(var) = variables.variable(expr.global_id()) {
+                accesses.push(Access::Read(expr.global_id(), var));
+            }
+        }
+
+        ExprKind::Assign(inner) => {
+            let assign = &ast[inner];
+            expr_accesses(assign.rhs, variables, ast, accesses);
+
+            match variables.variable(assign.lhs.global_id()) {
+                Some(var) => accesses.push(Access::Write(Definition::Assign(expr.global_id()), var)),
+                None => expr_accesses(assign.lhs, variables, ast, accesses),
+            }
+        }
+
+        ExprKind::Binary(inner) => {
+            expr_accesses(ast[inner].lhs, variables, ast, accesses);
+            expr_accesses(ast[inner].rhs, variables, ast, accesses);
+        }
+
+        ExprKind::Unary(inner) => expr_accesses(ast[inner].operand, variables, ast, accesses),
+
+        ExprKind::Call(inner) => {
+            expr_accesses(ast[inner].lhs, variables, ast, accesses);
+
+            for argument in ast[inner].arguments.iter().copied() {
+                expr_accesses(argument, variables, ast, accesses);
+            }
+        }
+
+        ExprKind::String(_) | ExprKind::Natural(_) | ExprKind::Decimal(_) | ExprKind::Boolean(_) | ExprKind::Nil => (),
+    }
+}
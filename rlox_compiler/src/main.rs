@@ -1,9 +1,15 @@
+mod options;
+
+use options::{Backend, Command, Emit, Lint, Options};
+use rlox_ast::Ast;
+use rlox_cf_graph::build_cfg;
+use rlox_errors::{Diagnostics, Format};
 use rlox_source::{Source, SourceFile, SourceLibrary};
 use std::fs::read_to_string;
 use std::io;
 use std::io::Result as IoResult;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
 macro_rules! abort {
@@ -14,16 +20,34 @@ macro_rules! abort {
 }
 
 pub fn main() -> ExitCode {
-    let args: Vec<String> = std::env::args().collect();
+    let options = match options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => abort!("{message}"),
+    };
+
+    if let Some(code) = &options.explain {
+        return explain(code);
+    }
+
+    match (options.command, &options.input) {
+        (Command::Source, None) => prompt_mode(&options),
+        (Command::Source, Some(file_path)) => file_mode(file_path, &options),
+        (command, Some(file_path)) => bytecode_mode(file_path, command, &options),
+        (_, None) => abort!("No input file specified"),
+    }
+}
 
-    match args.len() {
-        1 => prompt_mode(),
-        2 => file_mode(&args[1]),
-        other => abort!("Too many arguments, {other}"),
+fn explain(code: &str) -> ExitCode {
+    match rlox_errors::codes::explain(code) {
+        Some(explanation) => {
+            print!("{explanation}");
+            ExitCode::SUCCESS
+        }
+        None => abort!("Unknown code {code:?}"),
     }
 }
 
-fn file_mode(file_path: &str) -> ExitCode {
+fn file_mode(file_path: &str, options: &Options) -> ExitCode {
     let mut library = SourceLibrary::default();
 
     let src_id = match read_source(file_path, &mut library) {
@@ -31,40 +55,246 @@ fn file_mode(file_path: &str) -> ExitCode {
         Err(err) => abort!("Could not read {file_path:?}: {err}"),
     };
 
-    compile(Source::File(src_id), &library[src_id].data, &library)
+    let mut diagnostics = diagnostics(options);
+
+    let exit_code = match (options.fix, options.emit) {
+        (true, _) => fix_mode(src_id, &library, options, &mut diagnostics),
+        (false, None) => compile(Source::File(src_id), &library[src_id].data, &library, options, &mut diagnostics),
+        (false, Some(emit)) => emit_mode(file_path, src_id, &library, emit, options, &mut diagnostics),
+    };
+
+    diagnostics.report(&library, options.error_format, options.color);
+
+    if options.fix {
+        fix(file_path, &library[src_id], src_id, &diagnostics);
+    }
+
+    exit_code
+}
+
+/// Only parses and lints the code, the edits come from their messages. The program
+/// never runs, it could loop forever or do anything on the way.
+fn fix_mode(src_id: usize, library: &SourceLibrary, options: &Options, diagnostics: &mut Diagnostics) -> ExitCode {
+    match parse(Source::File(src_id), &library[src_id].data, library, options, diagnostics) {
+        Some(_) => ExitCode::SUCCESS,
+        None => ExitCode::FAILURE,
+    }
+}
+
+/// Rewrites the file with the edits of the messages reported for it.
+fn fix(file_path: &str, file: &SourceFile, src_id: usize, diagnostics: &Diagnostics) {
+    let edits = diagnostics
+        .edits()
+        .iter()
+        .filter(|edit| edit.metadata.source == Source::File(src_id));
+
+    let (fixed, applied) = rlox_errors::fix::apply(&file.data, edits);
+
+    if applied == 0 {
+        return;
+    }
+
+    let fixes = match applied {
+        1 => "fix",
+        _ => "fixes",
+    };
+
+    match std::fs::write(&file.path, fixed) {
+        Ok(()) => eprintln!("Applied {applied} {fixes} to {file_path:?}"),
+        Err(err) => eprintln!("Could not write the fixes to {file_path:?}: {err}"),
+    }
+}
+
+fn emit_mode(
+    file_path: &str,
+    src_id: usize,
+    library: &SourceLibrary,
+    emit: Emit,
+    options: &Options,
+    diagnostics: &mut Diagnostics,
+) -> ExitCode {
+    let Some(ast) = parse(Source::File(src_id), &library[src_id].data, library, options, diagnostics) else {
+        return ExitCode::FAILURE;
+    };
+
+    let compiled = match options.backend {
+        Backend::TreeWalk | Backend::Vm => rlox_vm::compile(&ast),
+        Backend::Cfg => rlox_vm::compile_cfg(&build_cfg::from_sequence_of_stmts(ast.main(), &ast), &ast),
+    };
+
+    let chunk = match compiled {
+        Ok(chunk) => chunk,
+        Err(error) => {
+            diagnostics.error(error);
+            report(library, options, diagnostics);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match emit {
+        Emit::Bytecode => {
+            let output_path = Path::new(file_path).with_extension("loxb");
+            let bytes = rlox_vm::loxb::encode(&chunk, library);
+
+            if let Err(err) = std::fs::write(&output_path, bytes) {
+                abort!("Could not write {output_path:?}: {err}");
+            }
+        }
+
+        Emit::Disassembly => {
+            if let Err(err) = rlox_vm::disassemble(&chunk, library, &mut io::stdout()) {
+                abort!("Could not write the disassembly: {err}");
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn bytecode_mode(file_path: &str, command: Command, options: &Options) -> ExitCode {
+    let bytes = match std::fs::read(file_path) {
+        Ok(bytes) => bytes,
+        Err(err) => abort!("Could not read {file_path:?}: {err}"),
+    };
+
+    let mut library = SourceLibrary::default();
+
+    let chunk = match rlox_vm::loxb::decode(&bytes, &mut library) {
+        Ok(chunk) => chunk,
+        Err(err) => abort!("Could not load {file_path:?}: {err}"),
+    };
+
+    if command == Command::Disassemble {
+        if let Err(err) = rlox_vm::disassemble(&chunk, &library, &mut io::stdout()) {
+            abort!("Could not write the disassembly: {err}");
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
+    let mut diagnostics = diagnostics(options);
+
+    let exit_code = match rlox_vm::run(&chunk, &mut diagnostics) {
+        Ok(_eval_report) => ExitCode::SUCCESS,
+        Err(_) => ExitCode::FAILURE,
+    };
+
+    diagnostics.report(&library, options.error_format, options.color);
+    exit_code
 }
 
-fn prompt_mode() -> ! {
+fn prompt_mode(options: &Options) -> ! {
     let mut output = io::stdout();
     let mut buffer = String::new();
-    let library = SourceLibrary::default();
+    let mut library = SourceLibrary::default();
+    let mut line = 0;
 
     loop {
         write!(&mut output, "> ").unwrap();
         output.flush().unwrap();
 
-        io::stdin().read_line(&mut buffer).unwrap();
+        if io::stdin().read_line(&mut buffer).unwrap() == 0 {
+            std::process::exit(0);
+        }
 
-        compile(Source::Prompt, &buffer, &library);
+        // Every line is kept as a file of its own, so messages can show the code.
+        line += 1;
+        let src_id = library.add(SourceFile {
+            path: format!("<repl:{line}>"),
+            data: std::mem::take(&mut buffer),
+        });
 
-        buffer.clear();
+        let mut diagnostics = diagnostics(options);
+        compile(Source::File(src_id), &library[src_id].data, &library, options, &mut diagnostics);
+        diagnostics.report(&library, options.error_format, options.color);
     }
 }
 
-fn compile(src_id: Source, code: &str, library: &SourceLibrary) -> ExitCode {
-    let Ok(ast) = rlox_parser::parse(src_id, code.as_bytes()) else {
-        rlox_errors::report(library);
+/// Parses the code and reports the warnings of the lints before anything runs,
+/// the optimizations are applied after linting the code as it was written.
+fn parse(
+    src_id: Source,
+    code: &str,
+    library: &SourceLibrary,
+    options: &Options,
+    diagnostics: &mut Diagnostics,
+) -> Option<Ast> {
+    let Ok(mut ast) = rlox_parser::parse(src_id, code.as_bytes(), diagnostics) else {
+        report(library, options, diagnostics);
+        return None;
+    };
+
+    // Each line of the prompt is a program of its own, most of its variables are never read.
+    if options.input.is_some() {
+        rlox_lints::check(&ast, diagnostics);
+    }
+
+    // Denied warnings are errors, the program does not run.
+    let denied = diagnostics.has_errors();
+    report(library, options, diagnostics);
+
+    if denied {
+        return None;
+    }
+
+    if options.optimize {
+        rlox_optimizer::optimize(&mut ast);
+    }
+
+    Some(ast)
+}
+
+fn compile(
+    src_id: Source,
+    code: &str,
+    library: &SourceLibrary,
+    options: &Options,
+    diagnostics: &mut Diagnostics,
+) -> ExitCode {
+    let Some(ast) = parse(src_id, code, library, options, diagnostics) else {
         return ExitCode::FAILURE;
     };
 
-    let Ok(_eval_report) = rlox_interpreter::eval(&ast) else {
-        rlox_errors::report(library);
+    let eval_result = match options.backend {
+        Backend::TreeWalk => rlox_interpreter::eval(&ast, diagnostics),
+        Backend::Vm => rlox_vm::eval(&ast, diagnostics),
+        Backend::Cfg => rlox_vm::eval_cfg(&ast, diagnostics),
+    };
+
+    let Ok(_eval_report) = eval_result else {
+        report(library, options, diagnostics);
         return ExitCode::FAILURE;
     };
 
     ExitCode::SUCCESS
 }
 
+/// A new sink for the messages of a program, with the warnings allowed or denied in the options.
+fn diagnostics(options: &Options) -> Diagnostics {
+    let mut diagnostics = Diagnostics::new();
+
+    if options.deny_warnings {
+        diagnostics.deny_warnings();
+    }
+
+    for lint in &options.lints {
+        match *lint {
+            Lint::Allow(code) => diagnostics.allow(code),
+            Lint::Deny(code) => diagnostics.deny(code),
+        }
+    }
+
+    diagnostics
+}
+
+/// Writes the messages found so far, so warnings come before the output of the
+/// program. A SARIF log holds every message, it is written once everything ran.
+fn report(library: &SourceLibrary, options: &Options, diagnostics: &mut Diagnostics) {
+    if options.error_format != Format::Sarif {
+        diagnostics.report(library, options.error_format, options.color);
+    }
+}
+
 pub fn read_source<P: Into<PathBuf>>(path: P, library: &mut SourceLibrary) -> IoResult<usize> {
     let path: PathBuf = path.into().canonicalize()?;
     let source = read_to_string(&path)?;
@@ -0,0 +1,197 @@
+use rlox_errors::{Color, Format};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    TreeWalk,
+    Vm,
+    /// Bytecode generated from the control-flow graph.
+    Cfg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emit {
+    Bytecode,
+    Disassembly,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Command {
+    /// Runs lox source code, either from a file or from the prompt.
+    #[default]
+    Source,
+    /// Runs a `.loxb` file.
+    Run,
+    /// Prints the listing of a `.loxb` file.
+    Disassemble,
+}
+
+/// `--allow` and `--deny` of a warning code, the last one of a code wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lint {
+    Allow(&'static str),
+    Deny(&'static str),
+}
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub command: Command,
+    pub backend: Backend,
+    pub emit: Option<Emit>,
+    /// Runs the optimizations over the AST before compiling it.
+    pub optimize: bool,
+    pub error_format: Format,
+    pub color: Color,
+    /// Reports every warning as an error, set with `-W error`.
+    pub deny_warnings: bool,
+    pub lints: Vec<Lint>,
+    /// Applies the edits of the parser and lint messages to the input file, without
+    /// running it.
+    pub fix: bool,
+    /// Code of a message to explain instead of running anything.
+    pub explain: Option<String>,
+    pub input: Option<String>,
+}
+
+pub fn parse<I: Iterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.peekable();
+
+    match args.peek().map(String::as_str) {
+        Some("run") => options.command = Command::Run,
+        Some("disassemble") => options.command = Command::Disassemble,
+        _ => (),
+    }
+
+    if options.command != Command::Source {
+        args.next();
+    }
+
+    while let Some(arg) = args.next() {
+        if let Some(backend) = arg.strip_prefix("--backend=") {
+            options.backend = match backend {
+                "tree" => Backend::TreeWalk,
+                "vm" => Backend::Vm,
+                "cfg" => Backend::Cfg,
+                other => return Err(format!("Unknown backend {other:?}, expected \"tree\", \"vm\" or \"cfg\"")),
+            };
+
+            continue;
+        }
+
+        if let Some(emit) = arg.strip_prefix("--emit=") {
+            options.emit = match emit {
+                "bytecode" => Some(Emit::Bytecode),
+                "disassembly" => Some(Emit::Disassembly),
+                other => return Err(format!("Unknown emit {other:?}, expected \"bytecode\" or \"disassembly\"")),
+            };
+
+            continue;
+        }
+
+        if let Some(format) = arg.strip_prefix("--error-format=") {
+            options.error_format = match format {
+                "human" => Format::Human,
+                "json" => Format::Json,
+                "sarif" => Format::Sarif,
+                other => {
+                    return Err(format!("Unknown error format {other:?}, expected \"human\", \"json\" or \"sarif\""));
+                }
+            };
+
+            continue;
+        }
+
+        if let Some(color) = arg.strip_prefix("--color=") {
+            options.color = match color {
+                "auto" => Color::Auto,
+                "always" => Color::Always,
+                "never" => Color::Never,
+                other => return Err(format!("Unknown color {other:?}, expected \"auto\", \"always\" or \"never\"")),
+            };
+
+            continue;
+        }
+
+        if arg == "--explain" {
+            let Some(code) = args.next() else {
+                return Err("--explain needs a code, like E0102".into());
+            };
+
+            options.explain = Some(code);
+            continue;
+        }
+
+        if arg == "-W" {
+            match args.next() {
+                Some(level) if level == "error" => options.deny_warnings = true,
+                Some(level) => return Err(format!("Unknown warning level {level:?}, expected \"error\"")),
+                None => return Err("-W needs a level, like error".into()),
+            }
+
+            continue;
+        }
+
+        if arg == "--allow" || arg == "--deny" {
+            let Some(name) = args.next() else {
+                return Err(format!("{arg} needs a warning, like unused-variable or W0101"));
+            };
+
+            let Some(code) = rlox_errors::codes::warning(&name) else {
+                return Err(format!("Unknown warning {name:?}"));
+            };
+
+            options.lints.push(match arg.as_str() {
+                "--allow" => Lint::Allow(code),
+                _ => Lint::Deny(code),
+            });
+
+            continue;
+        }
+
+        if arg == "--fix" {
+            options.fix = true;
+            continue;
+        }
+
+        if arg == "-O" {
+            options.optimize = true;
+            continue;
+        }
+
+        if arg.starts_with('-') {
+            return Err(format!("Unknown option {arg:?}"));
+        }
+
+        if let Some(input) = &options.input {
+            return Err(format!("Too many input files, {input:?} and {arg:?}"));
+        }
+
+        options.input = Some(arg);
+    }
+
+    if options.explain.is_some() {
+        return match options.input.is_some() || options.command != Command::Source {
+            true => Err("--explain does not take an input file".into()),
+            false => Ok(options),
+        };
+    }
+
+    if options.input.is_none() && (options.command != Command::Source || options.emit.is_some()) {
+        return Err("No input file specified".into());
+    }
+
+    if options.command != Command::Source && options.emit.is_some() {
+        return Err("--emit only applies to lox source files".into());
+    }
+
+    if options.fix && (options.command != Command::Source || options.input.is_none()) {
+        return Err("--fix only applies to lox source files".into());
+    }
+
+    if options.fix && options.emit.is_some() {
+        return Err("--fix does not emit anything, it only fixes the file".into());
+    }
+
+    Ok(options)
+}
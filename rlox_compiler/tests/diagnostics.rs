@@ -0,0 +1,214 @@
+//! How `loxc` reports problems in the programs it runs.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn loxc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_loxc"))
+        .args(args)
+        .output()
+        .expect("loxc should run")
+}
+
+/// Types every line of the input in the prompt.
+fn prompt(input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_loxc"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("loxc should run");
+
+    child
+        .stdin
+        .take()
+        .expect("the prompt should read its input")
+        .write_all(input.as_bytes())
+        .expect("the input should be written");
+
+    child.wait_with_output().expect("loxc should finish")
+}
+
+/// Writes the program to a file of its own, named after the test.
+fn program(name: &str, code: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("loxc_diagnostics_{name}.lox"));
+    let mut file = std::fs::File::create(&path).expect("the program should be written");
+    file.write_all(code.as_bytes())
+        .expect("the program should be written");
+
+    path
+}
+
+#[test]
+fn messages_include_their_code() {
+    let path = program("codes", "var a = \"text\";\nprintln(a + 1);\n");
+    let output = loxc(&[path.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stderr.contains("[WARNING W0107] This operation always fails"), "{stderr}");
+    assert!(stderr.contains("[ERROR E0201] The operation is not defined."), "{stderr}");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn codes_are_explained() {
+    let output = loxc(&["--explain", "E0102"]);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("The parser found a token"));
+}
+
+#[test]
+fn unknown_codes_are_rejected() {
+    let output = loxc(&["--explain", "E9999"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown code \"E9999\""));
+}
+
+#[test]
+fn json_has_one_object_per_message() {
+    let path = program("json", "var a;\nprintln(a);\nvar b = 1;\n");
+    let output = loxc(&["--error-format=json", path.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let objects: Vec<_> = stderr
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .collect();
+    assert_eq!(objects.len(), 2, "{stderr}");
+    assert!(
+        objects[1]
+            .starts_with(r#"{"severity":"warning","code":"W0101","message":"Variable `b` is declared but never read""#)
+    );
+    assert!(
+        objects[0]
+            .contains(r#""span":{"start":15,"end":16,"start_line":2,"start_column":9,"end_line":2,"end_column":10}"#)
+    );
+}
+
+#[test]
+fn sarif_is_a_single_log_apart_from_the_output() {
+    let path = program("sarif", "println(1);\nprintln(1 + true);\n");
+    let output = loxc(&["--error-format=sarif", path.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let lines: Vec<_> = stderr.lines().collect();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    assert_eq!(lines.len(), 1, "{stderr}");
+    assert!(lines[0].starts_with(r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json""#));
+    assert!(lines[0].contains(r#""results":[{"ruleId":"W0107","ruleIndex":1,"level":"warning""#));
+    assert!(lines[0].contains(r#"{"ruleId":"E0201","ruleIndex":0,"level":"error""#));
+}
+
+#[test]
+fn prompt_lines_are_shown_as_numbered_files() {
+    let output = prompt("println(1);\nprintln(2 + true);\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stderr.contains("[ERROR E0201] The operation is not defined."), "{stderr}");
+    assert!(stderr.contains(" --> <repl:2>:1:9\n"), "{stderr}");
+    assert!(stderr.contains("1 | println(2 + true);\n"), "{stderr}");
+}
+
+#[test]
+fn colors_can_be_forced() {
+    let path = program("colors", "println(1 + true);\n");
+    let colored = loxc(&["--color=always", path.to_str().unwrap()]);
+    let plain = loxc(&["--color=never", path.to_str().unwrap()]);
+
+    assert!(String::from_utf8_lossy(&colored.stderr).contains("\x1b[1;31m[ERROR E0201]\x1b[0m"));
+    assert!(String::from_utf8_lossy(&plain.stderr).contains("\n[ERROR E0201]"));
+    assert_eq!(colored.stdout, plain.stdout);
+}
+
+#[test]
+fn typos_get_suggestions() {
+    let path = program("typos", "var counter = 1;\nprintln(countr);\n");
+    let stderr = String::from_utf8_lossy(&loxc(&[path.to_str().unwrap()]).stderr).to_string();
+    assert!(stderr.contains("= help: Maybe you meant `counter`."), "{stderr}");
+
+    let path = program("keywords", "whlie true { }\n");
+    let stderr = String::from_utf8_lossy(&loxc(&[path.to_str().unwrap()]).stderr).to_string();
+    assert!(stderr.contains("| ----- similar to the keyword `while`"), "{stderr}");
+}
+
+#[test]
+fn warnings_can_be_denied_or_allowed() {
+    let path = program("levels", "var a = 1;\nvar b = 2;\nprintln(b);\n");
+    let path = path.to_str().unwrap();
+
+    let denied = loxc(&["-W", "error", path]);
+    assert!(String::from_utf8_lossy(&denied.stderr).starts_with("[ERROR W0101]"));
+    assert!(denied.stdout.is_empty());
+    assert!(!denied.status.success());
+
+    let allowed = loxc(&["-W", "error", "--allow", "unused-variable", path]);
+    assert!(allowed.stderr.is_empty());
+    assert_eq!(String::from_utf8_lossy(&allowed.stdout), "2\n");
+}
+
+#[test]
+fn comments_allow_warnings_in_the_next_statement_or_the_file() {
+    let code = "// lox-allow: unused-variable\nvar a = 1;\nvar b = 2;\n";
+    let output = loxc(&[program("allow", code).to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("`a`") && stderr.contains("`b`"), "{stderr}");
+
+    let code = "var a = 1;\n// lox-allow-file: W0101\nvar b = 2;\n";
+    let output = loxc(&[program("allow_file", code).to_str().unwrap()]);
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn fixes_are_applied_in_place() {
+    let path = program("fix", "var a = 1\nprintln((a + 1);\nwhlie a < 3 {\n    a = a + 1\n}\n");
+    let path = path.to_str().unwrap();
+
+    // Statements with an error hide the ones after them, a second run fixes the rest.
+    assert!(!loxc(&["--fix", path]).status.success());
+    assert!(!loxc(&["--fix", path]).status.success());
+
+    let fixed = std::fs::read_to_string(path).unwrap();
+    assert_eq!(fixed, "var a = 1;\nprintln((a + 1));\nwhile a < 3 {\n    a = a + 1;\n}\n");
+    assert!(loxc(&[path]).status.success());
+}
+
+#[test]
+fn fixes_never_run_the_program_nor_guess_names() {
+    let path = program("fix_guess", "var a = 1\nprintln(a);\nprintln(b);\n");
+    let path = path.to_str().unwrap();
+    assert!(!loxc(&["--fix", path]).status.success());
+
+    // The code parses now, running it would print `1` and fail on `b`.
+    let output = loxc(&["--fix", path]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    let fixed = std::fs::read_to_string(path).unwrap();
+    assert_eq!(fixed, "var a = 1;\nprintln(a);\nprintln(b);\n");
+}
+
+#[test]
+fn fixes_never_write_unsupported_keywords() {
+    let path = program("fix_reserved", "retrun 1;\nwhlie false { }\n");
+    let path = path.to_str().unwrap();
+    let output = loxc(&["--fix", path]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    let fixed = std::fs::read_to_string(path).unwrap();
+    assert_eq!(fixed, "retrun 1;\nwhile false { }\n");
+}
+
+#[test]
+fn json_messages_include_their_edits() {
+    let path = program("edits", "var count = 1\nprintln(count);\n");
+    let output = loxc(&["--error-format=json", path.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stderr.contains(r#""edits":[{"file":"#), "{stderr}");
+    assert!(stderr.contains(r#""span":{"start":13,"end":13,"start_line":1,"start_column":14,"end_line":1,"end_column":14},"replacement":";"}]"#), "{stderr}");
+}
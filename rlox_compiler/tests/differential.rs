@@ -0,0 +1,77 @@
+//! Runs every program in `test_code` with each backend and checks
+//! that all of them behave exactly like the tree-walk interpreter,
+//! with and without optimizations.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const BACKENDS: &[&str] = &["--backend=vm", "--backend=cfg"];
+
+fn test_programs() -> Vec<PathBuf> {
+    let test_code = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_code");
+
+    let mut programs: Vec<_> = std::fs::read_dir(test_code)
+        .expect("test_code should be readable")
+        .map(|entry| entry.expect("test_code entries should be readable").path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "lox"))
+        .collect();
+
+    programs.sort();
+    programs
+}
+
+fn loxc(args: &[&str], program: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_loxc"))
+        .args(args)
+        .arg(program)
+        .output()
+        .expect("loxc should run")
+}
+
+#[test]
+fn backends_match_tree_walk_interpreter() {
+    let programs = test_programs();
+    assert!(!programs.is_empty());
+
+    for program in programs {
+        let expected = loxc(&["--backend=tree"], &program);
+
+        for backend in BACKENDS {
+            let found = loxc(&[backend], &program);
+
+            assert_eq!(
+                String::from_utf8_lossy(&expected.stdout),
+                String::from_utf8_lossy(&found.stdout),
+                "{backend} output differs for {program:?}"
+            );
+            assert_eq!(
+                String::from_utf8_lossy(&expected.stderr),
+                String::from_utf8_lossy(&found.stderr),
+                "{backend} messages differ for {program:?}"
+            );
+            assert_eq!(expected.status, found.status, "{backend} status differs for {program:?}");
+        }
+    }
+}
+
+#[test]
+fn optimizations_do_not_change_behavior() {
+    for program in test_programs() {
+        for backend in ["--backend=tree"].iter().chain(BACKENDS) {
+            let expected = loxc(&[backend], &program);
+            let found = loxc(&[backend, "-O"], &program);
+
+            assert_eq!(
+                String::from_utf8_lossy(&expected.stdout),
+                String::from_utf8_lossy(&found.stdout),
+                "{backend} -O output differs for {program:?}"
+            );
+            assert_eq!(
+                String::from_utf8_lossy(&expected.stderr),
+                String::from_utf8_lossy(&found.stderr),
+                "{backend} -O messages differ for {program:?}"
+            );
+            assert_eq!(expected.status, found.status, "{backend} -O status differs for {program:?}");
+        }
+    }
+}
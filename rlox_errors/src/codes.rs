@@ -0,0 +1,91 @@
+//! Stable codes of the messages. Errors start with `E` and warnings with `W`, the
+//! first two digits tell where they come from: `01` the parser, `02` the runtime,
+//! `03` the bytecode compiler and `W01` the lints. Codes are never reused.
+
+#[rustfmt::skip]
+const EXPLANATIONS: &[(&str, &str)] = &[
+    ("E0101", include_str!("explanations/E0101.md")),
+    ("E0102", include_str!("explanations/E0102.md")),
+    ("E0103", include_str!("explanations/E0103.md")),
+    ("E0104", include_str!("explanations/E0104.md")),
+    ("E0201", include_str!("explanations/E0201.md")),
+    ("E0202", include_str!("explanations/E0202.md")),
+    ("E0203", include_str!("explanations/E0203.md")),
+    ("E0204", include_str!("explanations/E0204.md")),
+    ("E0205", include_str!("explanations/E0205.md")),
+    ("E0301", include_str!("explanations/E0301.md")),
+    ("E0302", include_str!("explanations/E0302.md")),
+    ("E0303", include_str!("explanations/E0303.md")),
+    ("E0304", include_str!("explanations/E0304.md")),
+    ("W0101", include_str!("explanations/W0101.md")),
+    ("W0102", include_str!("explanations/W0102.md")),
+    ("W0103", include_str!("explanations/W0103.md")),
+    ("W0104", include_str!("explanations/W0104.md")),
+    ("W0105", include_str!("explanations/W0105.md")),
+    ("W0106", include_str!("explanations/W0106.md")),
+    ("W0107", include_str!("explanations/W0107.md")),
+    ("W0108", include_str!("explanations/W0108.md")),
+    ("W0109", include_str!("explanations/W0109.md")),
+];
+
+/// Names of the warnings, for `--allow`, `--deny` and `lox-allow` comments.
+#[rustfmt::skip]
+const NAMES: &[(&str, &str)] = &[
+    ("W0101", "unused-variable"),
+    ("W0102", "dead-store"),
+    ("W0103", "uninitialized-read"),
+    ("W0104", "maybe-uninitialized-read"),
+    ("W0105", "unreachable-code"),
+    ("W0106", "constant-condition"),
+    ("W0107", "undefined-operation"),
+    ("W0108", "non-boolean-condition"),
+    ("W0109", "not-callable"),
+];
+
+/// Code of a warning given its code, in any case, or its name.
+pub fn warning(code_or_name: &str) -> Option<&'static str> {
+    NAMES
+        .iter()
+        .find(|(code, name)| code.eq_ignore_ascii_case(code_or_name) || *name == code_or_name)
+        .map(|(code, _)| *code)
+}
+
+/// Long explanation of a code, with examples. Codes are not case sensitive.
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(code))
+        .map(|(_, explanation)| *explanation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_sorted_and_unique() {
+        assert!(EXPLANATIONS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn every_warning_has_a_name() {
+        let warnings = EXPLANATIONS
+            .iter()
+            .filter(|(code, _)| code.starts_with('W'));
+
+        assert!(
+            warnings
+                .map(|(code, _)| *code)
+                .eq(NAMES.iter().map(|(code, _)| *code))
+        );
+        assert_eq!(warning("dead-store"), Some("W0102"));
+        assert_eq!(warning("w0102"), Some("W0102"));
+        assert_eq!(warning("E0102"), None);
+    }
+
+    #[test]
+    fn codes_are_found_in_any_case() {
+        assert!(explain("e0102").is_some_and(|explanation| explanation.contains("println(a);")));
+        assert!(explain("E9999").is_none());
+    }
+}
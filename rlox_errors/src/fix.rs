@@ -0,0 +1,58 @@
+//! Applies the [`Edit`]s of the messages to the code, like `loxc --fix` does.
+
+use crate::Edit;
+
+/// The code with the edits applied, and how many of them were. Edits overlapping
+/// one applied before, in the order of the code, are left out, and so are repeated
+/// ones. Insertions at the same offset are applied in the order they are given.
+pub fn apply<'a, I: IntoIterator<Item = &'a Edit>>(code: &str, edits: I) -> (String, usize) {
+    let mut edits: Vec<&Edit> = edits.into_iter().collect();
+    edits.sort_by_key(|edit| (edit.metadata.start, edit.metadata.end));
+    edits.dedup();
+
+    let mut fixed = String::with_capacity(code.len());
+    let mut copied = 0;
+    let mut applied = 0;
+
+    for edit in edits {
+        let (start, end) = (edit.metadata.start, edit.metadata.end);
+
+        if start < copied || end > code.len() || !code.is_char_boundary(start) || !code.is_char_boundary(end) {
+            continue;
+        }
+
+        fixed.push_str(&code[copied..start]);
+        fixed.push_str(&edit.replacement);
+        copied = end;
+        applied += 1;
+    }
+
+    fixed.push_str(&code[copied..]);
+
+    (fixed, applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_source::{Source, SourceMetadata};
+
+    use super::*;
+
+    fn edit(start: usize, end: usize, replacement: &str) -> Edit {
+        Edit {
+            metadata: SourceMetadata {
+                start,
+                end,
+                source: Source::File(0),
+            },
+            replacement: replacement.into(),
+        }
+    }
+
+    #[test]
+    fn overlapping_edits_are_left_out() {
+        let edits = [edit(15, 15, ";"), edit(0, 5, "while"), edit(3, 8, "x"), edit(15, 15, ";")];
+
+        assert_eq!(apply("whlie a { b = 1 }", &edits), ("while a { b = 1; }".into(), 2));
+    }
+}
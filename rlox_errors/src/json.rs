@@ -0,0 +1,178 @@
+//! Messages as JSON, one object per message:
+//!
+//! ```json
+//! {"severity":"warning","code":"W0103","message":"Variable `a` is always nil here, ...",
+//!  "file":"test.lox","span":{...},"label":"read here",
+//!  "labels":[{"message":"declared here without a value","file":"test.lox","span":{...}}],
+//!  "notes":[],"help":"Give `a` a value when declaring it","edits":[]}
+//! ```
+//!
+//! Spans have the byte range, `start` and `end`, and the lines and columns where they
+//! start and end, all of them starting at one. The end is exclusive. Code without a
+//! file has a null `file`, and its spans only have the byte range. Edits replace the
+//! code of their span with `replacement`.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use rlox_source::{Source, SourceLibrary, SourceMetadata};
+
+use crate::{Message, Severity, render};
+
+/// The few JSON values needed to describe messages.
+pub(crate) enum Json {
+    Null,
+    Number(usize),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    pub(crate) fn string<S: Into<String>>(value: S) -> Json {
+        Json::String(value.into())
+    }
+
+    pub(crate) fn optional<T, F: FnOnce(T) -> Json>(value: Option<T>, to_json: F) -> Json {
+        value.map_or(Json::Null, to_json)
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Json::Null => "null".fmt(f),
+            Json::Number(number) => number.fmt(f),
+            Json::String(string) => write_string(f, string),
+
+            Json::Array(values) => {
+                "[".fmt(f)?;
+
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        ",".fmt(f)?;
+                    }
+
+                    value.fmt(f)?;
+                }
+
+                "]".fmt(f)
+            }
+
+            Json::Object(members) => {
+                "{".fmt(f)?;
+
+                for (index, (name, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        ",".fmt(f)?;
+                    }
+
+                    write_string(f, name)?;
+                    ":".fmt(f)?;
+                    value.fmt(f)?;
+                }
+
+                "}".fmt(f)
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut Formatter<'_>, string: &str) -> FmtResult {
+    "\"".fmt(f)?;
+
+    for c in string.chars() {
+        match c {
+            '"' => "\\\"".fmt(f)?,
+            '\\' => "\\\\".fmt(f)?,
+            '\n' => "\\n".fmt(f)?,
+            '\r' => "\\r".fmt(f)?,
+            '\t' => "\\t".fmt(f)?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => c.fmt(f)?,
+        }
+    }
+
+    "\"".fmt(f)
+}
+
+pub(crate) fn message<M: Message + ?Sized>(severity: Severity, msg: &M, library: &SourceLibrary) -> Json {
+    let metadata = msg.source_metadata();
+
+    let labels = msg
+        .labels()
+        .into_iter()
+        .map(|label| {
+            Json::Object(vec![
+                ("message", Json::String(label.message)),
+                ("file", file(label.metadata, library)),
+                ("span", span(label.metadata, library)),
+            ])
+        })
+        .collect();
+
+    let edits = msg
+        .edits()
+        .into_iter()
+        .map(|edit| {
+            Json::Object(vec![
+                ("file", file(edit.metadata, library)),
+                ("span", span(edit.metadata, library)),
+                ("replacement", Json::String(edit.replacement)),
+            ])
+        })
+        .collect();
+
+    Json::Object(vec![
+        ("severity", Json::string(severity.name())),
+        ("code", Json::string(msg.code())),
+        ("message", Json::String(msg.description())),
+        ("file", file(metadata, library)),
+        ("span", span(metadata, library)),
+        ("label", Json::optional(msg.label(), Json::String)),
+        ("labels", Json::Array(labels)),
+        ("notes", Json::Array(msg.notes().into_iter().map(Json::String).collect())),
+        ("help", Json::optional(msg.help(), Json::String)),
+        ("edits", Json::Array(edits)),
+    ])
+}
+
+fn file(metadata: SourceMetadata, library: &SourceLibrary) -> Json {
+    match metadata.source {
+        Source::File(index) => Json::string(library[index].path.as_str()),
+        Source::Prompt => Json::Null,
+    }
+}
+
+fn span(metadata: SourceMetadata, library: &SourceLibrary) -> Json {
+    let Source::File(index) = metadata.source else {
+        return Json::Object(vec![("start", Json::Number(metadata.start)), ("end", Json::Number(metadata.end))]);
+    };
+
+    let file = &library[index];
+    let (start, end) = render::trim(file, metadata.start, metadata.end);
+    let (start_position, end_position) = (file.position(start), file.position(end));
+
+    Json::Object(vec![
+        ("start", Json::Number(start)),
+        ("end", Json::Number(end)),
+        ("start_line", Json::Number(start_position.line)),
+        ("start_column", Json::Number(start_position.column)),
+        ("end_line", Json::Number(end_position.line)),
+        ("end_column", Json::Number(end_position.column)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_are_escaped() {
+        let json = Json::Object(vec![
+            ("text", Json::string("a \"quoted\"\\path\n\u{1}")),
+            ("list", Json::Array(vec![Json::Number(1), Json::Null])),
+        ]);
+
+        assert_eq!(json.to_string(), r#"{"text":"a \"quoted\"\\path\n\u0001","list":[1,null]}"#);
+    }
+}
@@ -1,10 +1,112 @@
-use rlox_source::{Source, SourceLibrary, SourceMetadata};
-use std::io::{Stdout, Write, stdout};
-use std::sync::{Arc, LazyLock, Mutex};
+pub mod codes;
+pub mod fix;
+
+mod json;
+mod render;
+mod sarif;
+
+use rlox_source::{SourceLibrary, SourceMetadata};
+use std::collections::HashMap;
+use std::io::{IsTerminal, Result as IoResult, Write, stderr};
+use std::sync::Arc;
 
 pub trait Message: Sync + Send + 'static {
+    /// Stable identifier of the message, see [`codes`].
+    fn code(&self) -> &'static str;
     fn description(&self) -> String;
     fn source_metadata(&self) -> SourceMetadata;
+
+    /// Short explanation written next to the code of [`Message::source_metadata`].
+    fn label(&self) -> Option<String> {
+        None
+    }
+
+    /// Other parts of the code involved in the message.
+    fn labels(&self) -> Vec<Label> {
+        Vec::new()
+    }
+
+    /// Extra context, written after the code.
+    fn notes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// How the problem could be solved.
+    fn help(&self) -> Option<String> {
+        None
+    }
+
+    /// Changes to the code that solve the problem, safe to apply without reviewing them.
+    fn edits(&self) -> Vec<Edit> {
+        Vec::new()
+    }
+}
+
+/// How [`Diagnostics::report`] writes the messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Text for people, with the code of every message underlined.
+    #[default]
+    Human,
+    /// One JSON object per line and message.
+    Json,
+    /// A single SARIF log with every message, for code scanning tools.
+    Sarif,
+}
+
+/// When [`Format::Human`] writes the messages with colors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Color {
+    /// Only when the messages go to a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    fn enabled(self) -> bool {
+        match self {
+            Color::Auto => stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    /// Something worth knowing about the program, not a problem.
+    Note,
+    /// A suggestion to improve the program.
+    Help,
+}
+
+impl Severity {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+            Severity::Help => "help",
+        }
+    }
+}
+
+/// Part of the code with an explanation of its role in a message.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub message: String,
+    pub metadata: SourceMetadata,
+}
+
+/// Replaces the code of `metadata` with `replacement`, an empty span inserts it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub metadata: SourceMetadata,
+    pub replacement: String,
 }
 
 pub struct Error(Arc<dyn Message>);
@@ -21,67 +123,158 @@ impl<T: Message> From<T> for Warning {
     }
 }
 
-type Buffer = Vec<Arc<dyn Message>>;
-
-static ERRORS: LazyLock<Mutex<Buffer>> = LazyLock::new(|| Mutex::new(Buffer::default()));
-static WARNINGS: LazyLock<Mutex<Buffer>> = LazyLock::new(|| Mutex::new(Buffer::default()));
-
-pub fn error<E: Into<Error>>(error: E) {
-    let error: Error = error.into();
-    let mut error_log = ERRORS.lock().unwrap();
-    error_log.push(error.0);
+/// What is done with the warnings of a code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Allow,
+    Deny,
 }
 
-pub fn warning<W: Into<Warning>>(warning: W) {
-    let warning: Warning = warning.into();
-    let mut warning_log = WARNINGS.lock().unwrap();
-    warning_log.push(warning.0);
+/// Messages found while working on a program. Each program, or each line of the
+/// prompt, has its own, so messages never leak from one to another.
+///
+/// Warnings can be allowed, and then they are not reported, or denied, and then they
+/// are reported as errors. Allowing a code in part of the code wins over the rest.
+#[derive(Default)]
+pub struct Diagnostics {
+    messages: Vec<(Severity, Arc<dyn Message>)>,
+    levels: HashMap<&'static str, Level>,
+    deny_warnings: bool,
+    allowed_in: Vec<(&'static str, SourceMetadata)>,
+    edits: Vec<Edit>,
 }
 
-pub fn report(library: &SourceLibrary) {
-    let warnings: Buffer = std::mem::take(WARNINGS.lock().unwrap().as_mut());
-    let errors: Buffer = std::mem::take(ERRORS.lock().unwrap().as_mut());
-    let mut stdout = stdout();
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics::default()
+    }
 
-    for warning in warnings {
-        let message = warning.description();
-        writeln!(&mut stdout, "[WARNING] {message}.").unwrap();
-        print_source(&mut stdout, warning.as_ref(), library);
+    pub fn error<E: Into<Error>>(&mut self, error: E) {
+        self.messages.push((Severity::Error, error.into().0));
     }
 
-    for error in errors {
-        let message = error.description();
-        writeln!(&mut stdout, "[ERROR] {message}.").unwrap();
+    pub fn warning<W: Into<Warning>>(&mut self, warning: W) {
+        let warning = warning.into().0;
 
-        print_source(&mut stdout, error.as_ref(), library);
+        if let Some(severity) = self.warning_severity(warning.as_ref()) {
+            self.messages.push((severity, warning));
+        }
     }
-}
 
-fn print_source<Msg>(stdout: &mut Stdout, msg: &Msg, library: &SourceLibrary)
-where
-    Msg: Message + ?Sized,
-{
-    let metadata = msg.source_metadata();
+    pub fn note<M: Message>(&mut self, note: M) {
+        self.messages.push((Severity::Note, Arc::new(note)));
+    }
 
-    let Source::File(index) = metadata.source else {
-        return;
-    };
+    pub fn help<M: Message>(&mut self, help: M) {
+        self.messages.push((Severity::Help, Arc::new(help)));
+    }
+
+    /// Warnings of `code` are not reported, unless it is denied afterwards.
+    pub fn allow(&mut self, code: &'static str) {
+        self.levels.insert(code, Level::Allow);
+    }
 
-    let source = &library[index];
-    let source_path = &source.path;
+    /// Warnings of `code` are reported as errors, unless it is allowed afterwards.
+    pub fn deny(&mut self, code: &'static str) {
+        self.levels.insert(code, Level::Deny);
+    }
 
-    let data_as_bytes = source.data.as_bytes();
-    let relevant_part = String::from_utf8_lossy(&data_as_bytes[metadata.start..metadata.end]);
-    let mut line_offset = 1 + data_as_bytes[..metadata.start]
-        .iter()
-        .filter(|&&b| b == b'\n')
-        .count();
+    /// Every warning is reported as an error, except the codes allowed or denied.
+    pub fn deny_warnings(&mut self) {
+        self.deny_warnings = true;
+    }
 
-    for line in relevant_part.lines() {
-        writeln!(stdout, "  {line_offset}| {line}").unwrap();
-        line_offset += 1;
+    /// Warnings of `code` starting inside `metadata` are not reported.
+    pub fn allow_in(&mut self, code: &'static str, metadata: SourceMetadata) {
+        self.allowed_in.push((code, metadata));
+    }
+
+    fn warning_severity(&self, warning: &dyn Message) -> Option<Severity> {
+        let (code, metadata) = (warning.code(), warning.source_metadata());
+
+        let allowed_here = self.allowed_in.iter().any(|(allowed, span)| {
+            *allowed == code && span.source == metadata.source && (span.start..span.end).contains(&metadata.start)
+        });
+
+        if allowed_here {
+            return None;
+        }
+
+        match self.levels.get(code) {
+            Some(Level::Allow) => None,
+            Some(Level::Deny) => Some(Severity::Error),
+            None if self.deny_warnings => Some(Severity::Error),
+            None => Some(Severity::Warning),
+        }
+    }
+
+    pub fn messages(&self, severity: Severity) -> impl Iterator<Item = &dyn Message> {
+        self.messages
+            .iter()
+            .filter(move |(kind, _)| *kind == severity)
+            .map(|(_, msg)| msg.as_ref())
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &dyn Message> {
+        self.messages(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &dyn Message> {
+        self.messages(Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Edits of the messages written so far.
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// Writes the messages found so far to stderr, so they are not mixed with the
+    /// output of the program, and forgets them.
+    pub fn report(&mut self, library: &SourceLibrary, format: Format, color: Color) {
+        self.write(&mut stderr().lock(), library, format, color.enabled())
+            .unwrap();
+    }
+
+    /// Writes the messages found so far, errors last, and forgets them.
+    pub fn write<W: Write>(
+        &mut self,
+        out: &mut W,
+        library: &SourceLibrary,
+        format: Format,
+        colored: bool,
+    ) -> IoResult<()> {
+        let mut messages = std::mem::take(&mut self.messages);
+        messages.sort_by_key(|(severity, _)| *severity == Severity::Error);
+
+        self.edits
+            .extend(messages.iter().flat_map(|(_, msg)| msg.edits()));
+
+        match format {
+            Format::Human => {
+                for (severity, msg) in messages {
+                    render::message(out, severity, msg.as_ref(), library, colored)?;
+                }
+            }
+
+            Format::Json => {
+                for (severity, msg) in messages {
+                    writeln!(out, "{}", json::message(severity, msg.as_ref(), library))?;
+                }
+            }
+
+            Format::Sarif => writeln!(out, "{}", sarif::log(&messages, library))?,
+        }
+
+        Ok(())
     }
-    writeln!(stdout, "At {source_path}").unwrap();
 }
 
 #[macro_export]
@@ -97,3 +290,84 @@ macro_rules! compiler_log {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use rlox_source::Source;
+
+    use super::*;
+
+    struct Unused;
+
+    impl Message for Unused {
+        fn code(&self) -> &'static str {
+            "W0101"
+        }
+
+        fn description(&self) -> String {
+            "Variable `a` is declared but never read".into()
+        }
+
+        fn source_metadata(&self) -> SourceMetadata {
+            SourceMetadata {
+                start: 0,
+                end: 0,
+                source: Source::Prompt,
+            }
+        }
+    }
+
+    #[test]
+    fn sessions_do_not_share_messages() {
+        let (mut first, second) = (Diagnostics::new(), Diagnostics::new());
+        first.warning(Unused);
+
+        assert_eq!(first.warnings().count(), 1);
+        assert!(!first.has_errors());
+        assert!(second.is_empty());
+    }
+
+    #[test]
+    fn allowed_warnings_are_dropped_and_denied_ones_are_errors() {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.deny_warnings();
+        diagnostics.warning(Unused);
+        assert!(diagnostics.has_errors());
+
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.deny_warnings();
+        diagnostics.allow("W0101");
+        diagnostics.warning(Unused);
+        assert!(diagnostics.is_empty());
+
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.deny("W0101");
+        diagnostics.allow_in("W0101", SourceMetadata {
+            start: 0,
+            end: 10,
+            source: Source::Prompt,
+        });
+        diagnostics.warning(Unused);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn written_messages_are_forgotten_errors_last() {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.error(Unused);
+        diagnostics.help(Unused);
+
+        let mut out = Vec::new();
+        diagnostics
+            .write(&mut out, &SourceLibrary::new(), Format::Json, false)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let severities: Vec<_> = out
+            .lines()
+            .filter_map(|line| line.split(',').next())
+            .collect();
+        assert_eq!(severities, [r#"{"severity":"help""#, r#"{"severity":"error""#]);
+        assert!(diagnostics.is_empty());
+    }
+}
@@ -0,0 +1,393 @@
+//! Text rendering of messages. The code of every span is printed with its line
+//! numbers and underlined, with `^` for the main span and `-` for the labels:
+//!
+//! ```text
+//! [WARNING W0104] Variable `a` may be nil here, some paths do not assign a value before reading it.
+//!  --> test.lox:3:9
+//!   |
+//! 1 | var a;
+//!   | ------ declared here without a value
+//!   ...
+//! 3 | println(a);
+//!   |         ^ read here
+//!   = help: Give `a` a value when declaring it.
+//! ```
+//!
+//! With colors, the severity and the marks of the main span use the color of the
+//! severity, the line numbers and the marks of the labels are blue.
+
+use std::borrow::Cow;
+use std::io::{Result as IoResult, Write};
+
+use rlox_source::{Source, SourceFile, SourceLibrary};
+
+use crate::{Message, Severity};
+
+struct Span {
+    start: usize,
+    end: usize,
+    label: Option<String>,
+    primary: bool,
+}
+
+const BOLD: &str = "1";
+const RED: &str = "1;31";
+const YELLOW: &str = "1;33";
+const GREEN: &str = "1;32";
+const CYAN: &str = "1;36";
+const BLUE: &str = "1;34";
+
+/// Wraps text in ANSI escape codes, when colors are enabled.
+#[derive(Clone, Copy)]
+struct Paint {
+    colored: bool,
+}
+
+impl Paint {
+    fn apply<'a, T: Into<Cow<'a, str>>>(&self, style: &str, text: T) -> Cow<'a, str> {
+        match self.colored {
+            true => Cow::Owned(format!("\x1b[{style}m{}\x1b[0m", text.into())),
+            false => text.into(),
+        }
+    }
+}
+
+impl Severity {
+    fn style(&self) -> &'static str {
+        match self {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => GREEN,
+            Severity::Help => CYAN,
+        }
+    }
+}
+
+/// Part of a span inside a single line, the column starts at zero.
+struct Underline<'a> {
+    line: usize,
+    column: usize,
+    width: usize,
+    label: Option<&'a str>,
+    primary: bool,
+}
+
+pub fn message<W, M>(out: &mut W, severity: Severity, msg: &M, library: &SourceLibrary, colored: bool) -> IoResult<()>
+where
+    W: Write,
+    M: Message + ?Sized,
+{
+    let paint = Paint {
+        colored,
+    };
+
+    let header = format!("[{} {}]", severity.name().to_uppercase(), msg.code());
+    let description = format!("{}.", msg.description());
+    writeln!(out, "{} {}", paint.apply(severity.style(), header), paint.apply(BOLD, description))?;
+
+    let primary = msg.source_metadata();
+    let mut groups = vec![(primary.source, vec![Span {
+        start: primary.start,
+        end: primary.end,
+        label: msg.label(),
+        primary: true,
+    }])];
+
+    for label in msg.labels() {
+        let span = Span {
+            start: label.metadata.start,
+            end: label.metadata.end,
+            label: Some(label.message),
+            primary: false,
+        };
+
+        match groups
+            .iter_mut()
+            .find(|(source, _)| *source == label.metadata.source)
+        {
+            Some((_, spans)) => spans.push(span),
+            None => groups.push((label.metadata.source, vec![span])),
+        }
+    }
+
+    // Code without a file, like the sources of a changed `.loxb`, can not be shown.
+    let files: Vec<(&SourceFile, Vec<Underline>, bool)> = groups
+        .iter()
+        .filter_map(|(source, spans)| match source {
+            Source::File(index) => Some((&library[*index], spans)),
+            Source::Prompt => None,
+        })
+        .map(|(file, spans)| {
+            let underlines = spans
+                .iter()
+                .flat_map(|span| underlines(file, span))
+                .collect();
+            (file, underlines, spans.iter().any(|span| span.primary))
+        })
+        .collect();
+
+    let width = files
+        .iter()
+        .flat_map(|(_, underlines, _)| underlines.iter())
+        .map(|underline| underline.line.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    for (file, mut underlines, has_primary) in files {
+        underlines.sort_by_key(|underline| (underline.line, !underline.primary, underline.column));
+
+        let first = underlines
+            .iter()
+            .find(|underline| underline.primary)
+            .or(underlines.first());
+
+        if let Some(first) = first {
+            let arrow = match has_primary {
+                true => "-->",
+                false => ":::",
+            };
+
+            let location = format!("{}:{}:{}", file.path, first.line, first.column + 1);
+            writeln!(out, "{:width$}{} {location}", "", paint.apply(BLUE, arrow))?;
+            writeln!(out, "{:width$} {}", "", paint.apply(BLUE, "|"))?;
+        }
+
+        let mut previous_line = None;
+
+        for underline in underlines.iter() {
+            if previous_line != Some(underline.line) {
+                if previous_line.is_some_and(|previous| previous + 1 < underline.line) {
+                    writeln!(out, "{:width$} {}", "", paint.apply(BLUE, "..."))?;
+                }
+
+                let gutter = format!("{:>width$} |", underline.line);
+                writeln!(out, "{} {}", paint.apply(BLUE, gutter), line_text(file, underline.line))?;
+                previous_line = Some(underline.line);
+            }
+
+            let text = line_text(file, underline.line);
+            let padding: String = text
+                .chars()
+                .take(underline.column)
+                .map(|c| match c {
+                    '\t' => '\t',
+                    _ => ' ',
+                })
+                .collect();
+
+            let (mark, style) = match underline.primary {
+                true => ("^", severity.style()),
+                false => ("-", BLUE),
+            };
+
+            let marks = match underline.label {
+                Some(label) => format!("{} {label}", mark.repeat(underline.width)),
+                None => mark.repeat(underline.width),
+            };
+
+            writeln!(out, "{:width$} {} {padding}{}", "", paint.apply(BLUE, "|"), paint.apply(style, marks))?;
+        }
+    }
+
+    for note in msg.notes() {
+        writeln!(out, "{:width$} {} {note}.", "", paint.apply(BOLD, "= note:"))?;
+    }
+
+    if let Some(help) = msg.help() {
+        writeln!(out, "{:width$} {} {help}.", "", paint.apply(BOLD, "= help:"))?;
+    }
+
+    Ok(())
+}
+
+/// Spans of statements include the whitespace after them, it is not underlined. Spans
+/// covering several lines are underlined in each one, the label goes in the last.
+fn underlines<'a>(file: &SourceFile, span: &'a Span) -> Vec<Underline<'a>> {
+    let (start, end) = trim(file, span.start, span.end);
+
+    let mut underlines = Vec::new();
+    let mut offset = start;
+
+    loop {
+        let line_start = file.line_start(offset);
+        let line_end = file.line_end(offset);
+        let segment_end = end.min(line_end);
+        let last = segment_end == end;
+
+        let width = text(file, offset, segment_end).chars().count();
+
+        // Empty lines in the middle of a span have nothing to underline.
+        if width > 0 || last {
+            underlines.push(Underline {
+                line: file.position(offset).line,
+                column: text(file, line_start, offset).chars().count(),
+                width: width.max(1),
+                label: last.then_some(span.label.as_deref()).flatten(),
+                primary: span.primary,
+            });
+        }
+
+        if last {
+            return underlines;
+        }
+
+        offset = line_end + 1;
+    }
+}
+
+/// Range of a span inside the file, without the whitespace at its end.
+pub(crate) fn trim(file: &SourceFile, start: usize, end: usize) -> (usize, usize) {
+    let length = file.data.len();
+    let start = start.min(length);
+    let end = start + text(file, start, end.clamp(start, length)).trim_end().len();
+
+    (start, end)
+}
+
+fn text(file: &SourceFile, start: usize, end: usize) -> Cow<'_, str> {
+    String::from_utf8_lossy(&file.data.as_bytes()[start..end])
+}
+
+fn line_text(file: &SourceFile, line: usize) -> &str {
+    file.data
+        .lines()
+        .nth(line - 1)
+        .unwrap_or_default()
+        .trim_end_matches('\r')
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_source::{SourceFile, SourceMetadata};
+
+    use super::*;
+    use crate::Label;
+
+    const CODE: &str = "var a;\nif true {\n    println(a);\n}\n";
+
+    struct Uninitialized;
+
+    impl Message for Uninitialized {
+        fn code(&self) -> &'static str {
+            "W0103"
+        }
+
+        fn description(&self) -> String {
+            "Variable `a` is always nil here".into()
+        }
+
+        fn source_metadata(&self) -> SourceMetadata {
+            SourceMetadata {
+                start: 29,
+                end: 30,
+                source: Source::File(0),
+            }
+        }
+
+        fn label(&self) -> Option<String> {
+            Some("read here".into())
+        }
+
+        fn labels(&self) -> Vec<Label> {
+            vec![Label {
+                message: "declared here without a value".into(),
+                metadata: SourceMetadata {
+                    start: 0,
+                    end: 7,
+                    source: Source::File(0),
+                },
+            }]
+        }
+
+        fn help(&self) -> Option<String> {
+            Some("Give `a` a value when declaring it".into())
+        }
+    }
+
+    struct Block;
+
+    impl Message for Block {
+        fn code(&self) -> &'static str {
+            "W0105"
+        }
+
+        fn description(&self) -> String {
+            "This code is never executed".into()
+        }
+
+        fn source_metadata(&self) -> SourceMetadata {
+            SourceMetadata {
+                start: 15,
+                end: CODE.len(),
+                source: Source::File(0),
+            }
+        }
+
+        fn notes(&self) -> Vec<String> {
+            vec!["The condition is always false".into()]
+        }
+    }
+
+    fn rendered<M: Message>(msg: &M) -> String {
+        rendered_as(msg, Severity::Warning, false)
+    }
+
+    fn rendered_as<M: Message>(msg: &M, severity: Severity, colored: bool) -> String {
+        let mut library = SourceLibrary::new();
+        library.add(SourceFile {
+            path: "test.lox".into(),
+            data: CODE.into(),
+        });
+
+        let mut out = Vec::new();
+        message(&mut out, severity, msg, &library, colored).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn labels_are_underlined_in_order() {
+        assert_eq!(
+            rendered(&Uninitialized),
+            "[WARNING W0103] Variable `a` is always nil here.
+ --> test.lox:3:13
+  |
+1 | var a;
+  | ------ declared here without a value
+  ...
+3 |     println(a);
+  |             ^ read here
+  = help: Give `a` a value when declaring it.
+"
+        );
+    }
+
+    #[test]
+    fn spans_over_several_lines_are_underlined_in_each_line() {
+        assert_eq!(
+            rendered(&Block),
+            "[WARNING W0105] This code is never executed.
+ --> test.lox:2:9
+  |
+2 | if true {
+  |         ^
+3 |     println(a);
+  | ^^^^^^^^^^^^^^^
+4 | }
+  | ^
+  = note: The condition is always false.
+"
+        );
+    }
+
+    #[test]
+    fn colors_follow_the_severity() {
+        let rendered = rendered_as(&Block, Severity::Note, true);
+        let lines: Vec<_> = rendered.lines().collect();
+
+        assert_eq!(lines[0], "\x1b[1;32m[NOTE W0105]\x1b[0m \x1b[1mThis code is never executed.\x1b[0m");
+        assert_eq!(lines[1], " \x1b[1;34m-->\x1b[0m test.lox:2:9");
+        assert_eq!(lines[3], "\x1b[1;34m2 |\x1b[0m if true {");
+        assert_eq!(lines[4], "  \x1b[1;34m|\x1b[0m         \x1b[1;32m^\x1b[0m");
+    }
+}
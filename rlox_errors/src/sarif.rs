@@ -0,0 +1,204 @@
+//! Messages as a [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+//! log, the format read by code scanning tools. The log has a single run with a
+//! result per message, and a rule per code with its explanation. Code without a file
+//! has messages that are results without locations.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use rlox_source::{Source, SourceLibrary, SourceMetadata};
+
+use crate::json::Json;
+use crate::{Message, Severity, codes, render};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+pub(crate) fn log(messages: &[(Severity, Arc<dyn Message>)], library: &SourceLibrary) -> Json {
+    let mut rule_codes: Vec<&'static str> = messages.iter().map(|(_, msg)| msg.code()).collect();
+    rule_codes.sort();
+    rule_codes.dedup();
+
+    let rules = rule_codes.iter().map(|code| rule(code)).collect();
+
+    let results = messages
+        .iter()
+        .map(|(severity, msg)| {
+            let rule_index = rule_codes
+                .binary_search(&msg.code())
+                .expect("every code has a rule");
+
+            result(*severity, msg.as_ref(), rule_index, library)
+        })
+        .collect();
+
+    let driver = Json::Object(vec![
+        ("name", Json::string("loxc")),
+        ("version", Json::string(env!("CARGO_PKG_VERSION"))),
+        ("rules", Json::Array(rules)),
+    ]);
+
+    let run = Json::Object(vec![("tool", Json::Object(vec![("driver", driver)])), ("results", Json::Array(results))]);
+
+    Json::Object(vec![
+        ("$schema", Json::string(SCHEMA)),
+        ("version", Json::string("2.1.0")),
+        ("runs", Json::Array(vec![run])),
+    ])
+}
+
+/// The first paragraph of the explanation is the short description.
+fn rule(code: &str) -> Json {
+    let explanation = codes::explain(code).unwrap_or_default();
+    let summary = explanation
+        .split("\n\n")
+        .next()
+        .unwrap_or_default()
+        .replace('\n', " ");
+
+    Json::Object(vec![
+        ("id", Json::string(code)),
+        ("shortDescription", Json::Object(vec![("text", Json::String(summary))])),
+        ("fullDescription", Json::Object(vec![("text", Json::string(explanation))])),
+    ])
+}
+
+fn result<M: Message + ?Sized>(severity: Severity, msg: &M, rule_index: usize, library: &SourceLibrary) -> Json {
+    let mut text = msg.description();
+
+    for note in msg.notes() {
+        text = format!("{text}\nNote: {note}.");
+    }
+
+    if let Some(help) = msg.help() {
+        text = format!("{text}\nHelp: {help}.");
+    }
+
+    let locations = location(msg.source_metadata(), None, msg.label(), library)
+        .into_iter()
+        .collect();
+
+    let related_locations = msg
+        .labels()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(id, label)| location(label.metadata, Some(id), Some(label.message), library))
+        .collect();
+
+    Json::Object(vec![
+        ("ruleId", Json::string(msg.code())),
+        ("ruleIndex", Json::Number(rule_index)),
+        ("level", Json::string(level(severity))),
+        ("message", Json::Object(vec![("text", Json::String(text))])),
+        ("locations", Json::Array(locations)),
+        ("relatedLocations", Json::Array(related_locations)),
+    ])
+}
+
+/// SARIF has no help level, suggestions are notes.
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Help => "note",
+        severity => severity.name(),
+    }
+}
+
+/// Related locations have an id, and locations with a label a message.
+fn location(
+    metadata: SourceMetadata,
+    id: Option<usize>,
+    message: Option<String>,
+    library: &SourceLibrary,
+) -> Option<Json> {
+    let Source::File(index) = metadata.source else {
+        return None;
+    };
+
+    let file = &library[index];
+    let (start, end) = render::trim(file, metadata.start, metadata.end);
+    let (start_position, end_position) = (file.position(start), file.position(end));
+
+    let region = Json::Object(vec![
+        ("startLine", Json::Number(start_position.line)),
+        ("startColumn", Json::Number(start_position.column)),
+        ("endLine", Json::Number(end_position.line)),
+        ("endColumn", Json::Number(end_position.column)),
+        ("byteOffset", Json::Number(start)),
+        ("byteLength", Json::Number(end - start)),
+    ]);
+
+    let physical_location = Json::Object(vec![
+        ("artifactLocation", Json::Object(vec![("uri", Json::String(uri(&file.path)))])),
+        ("region", region),
+    ]);
+
+    let mut members = Vec::new();
+
+    if let Some(id) = id {
+        members.push(("id", Json::Number(id)));
+    }
+
+    members.push(("physicalLocation", physical_location));
+
+    if let Some(message) = message {
+        members.push(("message", Json::Object(vec![("text", Json::String(message))])));
+    }
+
+    Some(Json::Object(members))
+}
+
+/// Absolute paths become `file` URIs, relative ones are already valid references.
+fn uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+
+    match Path::new(&path).is_absolute() {
+        true => format!("file://{path}"),
+        false => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_source::SourceFile;
+
+    use super::*;
+
+    struct Unused;
+
+    impl Message for Unused {
+        fn code(&self) -> &'static str {
+            "W0101"
+        }
+
+        fn description(&self) -> String {
+            "Variable `a` is declared but never read".into()
+        }
+
+        fn source_metadata(&self) -> SourceMetadata {
+            SourceMetadata {
+                start: 0,
+                end: 11,
+                source: Source::File(0),
+            }
+        }
+    }
+
+    #[test]
+    fn results_point_to_their_rule_and_region() {
+        let mut library = SourceLibrary::new();
+        library.add(SourceFile {
+            path: "/code/test.lox".into(),
+            data: "var a = 1;\n\n".into(),
+        });
+
+        let log = log(&[(Severity::Warning, Arc::new(Unused))], &library).to_string();
+
+        assert!(log.starts_with(r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0""#));
+        assert!(log.contains(r#""rules":[{"id":"W0101","shortDescription":{"text":"A variable is declared but its value is never read."}"#));
+        assert!(log.contains(
+            r#""results":[{"ruleId":"W0101","ruleIndex":0,"level":"warning","message":{"text":"Variable `a` is declared but never read"}"#
+        ));
+        assert!(log.contains(
+            r#""artifactLocation":{"uri":"file:///code/test.lox"},"region":{"startLine":1,"startColumn":1,"endLine":1,"endColumn":11,"byteOffset":0,"byteLength":10}"#
+        ));
+    }
+}
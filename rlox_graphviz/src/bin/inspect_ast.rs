@@ -1,6 +1,7 @@
 // Example of graphviz:
 // dot out/block.dot -T svg -o out/block.svg
 
+use rlox_errors::{Color, Diagnostics, Format};
 use rlox_source::{Source, SourceFile, SourceLibrary};
 use std::fs::File;
 use std::fs::read_to_string;
@@ -22,6 +23,15 @@ pub fn main() -> ExitCode {
         return ExitCode::FAILURE;
     };
 
+    let optimize = match args.get(3).map(String::as_str) {
+        None => false,
+        Some("-O") => true,
+        Some(other) => {
+            eprintln!("Unknown option {other}, expected -O");
+            return ExitCode::FAILURE;
+        }
+    };
+
     let mut library = SourceLibrary::default();
 
     let Ok(src_index) = read_source(file_path, &mut library) else {
@@ -32,15 +42,21 @@ pub fn main() -> ExitCode {
     let src_code = &library[src_index].data;
     let src_id = Source::File(src_index);
 
-    compile(src_id, src_code, &library, output_path)
+    compile(src_id, src_code, &library, output_path, optimize)
 }
 
-fn compile(src_id: Source, code: &str, library: &SourceLibrary, output_path: &str) -> ExitCode {
-    let Ok(ast) = rlox_parser::parse(src_id, code.as_bytes()) else {
-        rlox_errors::report(library);
+fn compile(src_id: Source, code: &str, library: &SourceLibrary, output_path: &str, optimize: bool) -> ExitCode {
+    let mut diagnostics = Diagnostics::new();
+
+    let Ok(mut ast) = rlox_parser::parse(src_id, code.as_bytes(), &mut diagnostics) else {
+        diagnostics.report(library, Format::Human, Color::Auto);
         return ExitCode::FAILURE;
     };
 
+    if optimize {
+        rlox_optimizer::optimize(&mut ast);
+    }
+
     let Ok(mut output) = File::create(output_path).map(BufWriter::new) else {
         eprintln!("Could not created graph.dot");
         return ExitCode::FAILURE;
@@ -8,10 +8,19 @@ use std::io::Result as IoResult;
 use std::path::PathBuf;
 use std::process::ExitCode;
 
-use rlox_cf_graph::build_cfg;
-use rlox_graphviz::cfg::Ctxt;
+use rlox_cf_graph::{build_cfg, call_graph};
+use rlox_errors::{Color, Diagnostics, Format};
+use rlox_graphviz::cfg::ProgramCtxt;
 use rlox_source::{Source, SourceFile, SourceLibrary};
 
+/// What is drawn for every procedure.
+enum View {
+    ControlFlow,
+    Dominators,
+    Ssa,
+    Loops,
+}
+
 pub fn main() -> ExitCode {
     let args: Vec<String> = std::env::args().collect();
 
@@ -25,6 +34,17 @@ pub fn main() -> ExitCode {
         return ExitCode::FAILURE;
     };
 
+    let view = match args.get(3).map(String::as_str) {
+        None => View::ControlFlow,
+        Some("--dominators") => View::Dominators,
+        Some("--ssa") => View::Ssa,
+        Some("--loops") => View::Loops,
+        Some(other) => {
+            eprintln!("Unknown option {other}, expected --dominators, --ssa or --loops");
+            return ExitCode::FAILURE;
+        }
+    };
+
     let mut library = SourceLibrary::default();
 
     let Ok(src_index) = read_source(file_path, &mut library) else {
@@ -35,12 +55,14 @@ pub fn main() -> ExitCode {
     let src_code = &library[src_index].data;
     let src_id = Source::File(src_index);
 
-    compile(src_id, src_code, &library, output_path)
+    compile(src_id, src_code, &library, output_path, view)
 }
 
-fn compile(src_id: Source, code: &str, library: &SourceLibrary, output_path: &str) -> ExitCode {
-    let Ok(ast) = rlox_parser::parse(src_id, code.as_bytes()) else {
-        rlox_errors::report(library);
+fn compile(src_id: Source, code: &str, library: &SourceLibrary, output_path: &str, view: View) -> ExitCode {
+    let mut diagnostics = Diagnostics::new();
+
+    let Ok(ast) = rlox_parser::parse(src_id, code.as_bytes(), &mut diagnostics) else {
+        diagnostics.report(library, Format::Human, Color::Auto);
         return ExitCode::FAILURE;
     };
 
@@ -49,15 +71,23 @@ fn compile(src_id: Source, code: &str, library: &SourceLibrary, output_path: &st
         return ExitCode::FAILURE;
     };
 
-    let main_sequence = ast.main();
-    let cf_graph = build_cfg::from_sequence_of_stmts(main_sequence, &ast);
-    let ctxt = Ctxt {
+    let program = build_cfg::from_program(&ast);
+    let call_graph = call_graph::build(&program, &ast);
+    let ctxt = ProgramCtxt {
         library,
         ast: &ast,
-        cf_graph: &cf_graph,
+        program: &program,
+        call_graph: &call_graph,
+    };
+
+    let result = match view {
+        View::ControlFlow => rlox_graphviz::cfg::program(ctxt, &mut output),
+        View::Dominators => rlox_graphviz::cfg::dominator_trees(ctxt, &mut output),
+        View::Ssa => rlox_graphviz::cfg::ssa_program(ctxt, &mut output),
+        View::Loops => rlox_graphviz::cfg::loop_forests(ctxt, &mut output),
     };
 
-    match rlox_graphviz::cfg::graph(ctxt, &mut output) {
+    match result {
         Ok(_) => ExitCode::SUCCESS,
         Err(error) => {
             eprintln!("Failed writing {error:?}");
@@ -3,7 +3,12 @@ use std::io::{BufWriter, Result, Write};
 use rlox_ast::Ast;
 use rlox_ast::expr::ExprId;
 use rlox_ast::stmt::StmtId;
-use rlox_cf_graph::{BasicBlock, BasicBlockId, BasicBlockValue, ControlFlowGraph, EdgeKind, Edges};
+use rlox_cf_graph::call_graph::CallGraph;
+use rlox_cf_graph::loops::{self, LoopForest, LoopId};
+use rlox_cf_graph::{
+    BasicBlock, BasicBlockId, BasicBlockValue, ControlFlowGraph, EdgeKind, Edges, Procedure, Program, Terminator,
+};
+use rlox_cf_graph::{dominators, ssa, variables};
 use rlox_infra::StructVec;
 use rlox_source::{Source, SourceLibrary};
 
@@ -14,17 +19,261 @@ pub struct Ctxt<'a> {
     pub library: &'a SourceLibrary,
 }
 
+#[derive(Clone, Copy)]
+pub struct ProgramCtxt<'a> {
+    pub program: &'a Program,
+    pub call_graph: &'a CallGraph,
+    pub ast: &'a Ast,
+    pub library: &'a SourceLibrary,
+}
+
 pub fn graph<W: Write>(ctxt: Ctxt, writer: &mut BufWriter<W>) -> Result<()> {
     writeln!(writer, "digraph {{")?;
 
     for bb_id in ctxt.cf_graph.basic_block_ids() {
-        graph_block(bb_id, ctxt, writer)?;
+        graph_block(bb_id, "", ctxt, writer)?;
+    }
+
+    writeln!(writer, "}}")?;
+    writer.flush()
+}
+
+/// Every procedure is drawn as a cluster, calls go from the
+/// block containing them to the entry point of the callee.
+pub fn program<W: Write>(ctxt: ProgramCtxt, writer: &mut BufWriter<W>) -> Result<()> {
+    writeln!(writer, "digraph {{")?;
+
+    for (index, (procedure, cf_graph)) in ctxt.program.procedures.iter().enumerate() {
+        let cluster_ctxt = Ctxt {
+            cf_graph,
+            ast: ctxt.ast,
+            library: ctxt.library,
+        };
+
+        let name = procedure_name(*procedure, ctxt.ast);
+
+        writeln!(writer, "subgraph cluster_{index} {{")?;
+        writeln!(writer, "label=\"{name}\"")?;
+
+        for bb_id in cf_graph.basic_block_ids() {
+            graph_block(bb_id, &procedure_prefix(*procedure, ctxt), cluster_ctxt, writer)?;
+        }
+
+        writeln!(writer, "}}")?;
+    }
+
+    for call in ctxt.call_graph.calls.iter() {
+        let callee = Procedure::Function(call.callee);
+        let Some(callee_graph) = ctxt.program.get(callee) else {
+            continue;
+        };
+
+        let caller_prefix = procedure_prefix(call.caller, ctxt);
+        let callee_prefix = procedure_prefix(callee, ctxt);
+        let entry_point = callee_graph.entry_point();
+
+        writeln!(
+            writer,
+            "\"{caller_prefix}{}\" -> \"{callee_prefix}{entry_point}\" [label=\"call\", style=\"dashed\"]",
+            call.block
+        )?;
+    }
+
+    writeln!(writer, "}}")?;
+    writer.flush()
+}
+
+/// Draws the dominator tree of every procedure as a cluster, using the same
+/// nodes as the control-flow graph and one edge per immediate dominator.
+pub fn dominator_trees<W: Write>(ctxt: ProgramCtxt, writer: &mut BufWriter<W>) -> Result<()> {
+    writeln!(writer, "digraph {{")?;
+
+    for (index, (procedure, cf_graph)) in ctxt.program.procedures.iter().enumerate() {
+        let cluster_ctxt = Ctxt {
+            cf_graph,
+            ast: ctxt.ast,
+            library: ctxt.library,
+        };
+
+        let name = procedure_name(*procedure, ctxt.ast);
+
+        let prefix = procedure_prefix(*procedure, ctxt);
+        let tree = dominators::dominators(cf_graph);
+
+        writeln!(writer, "subgraph cluster_{index} {{")?;
+        writeln!(writer, "label=\"{name}\"")?;
+
+        for bb_id in cf_graph
+            .basic_block_ids()
+            .filter(|bb_id| tree.is_reachable(*bb_id))
+        {
+            graph_node(bb_id, &prefix, cluster_ctxt, writer)?;
+
+            for child in tree.children(bb_id) {
+                writeln!(writer, "\"{prefix}{bb_id}\" -> \"{prefix}{child}\"")?;
+            }
+        }
+
+        writeln!(writer, "}}")?;
+    }
+
+    writeln!(writer, "}}")?;
+    writer.flush()
+}
+
+/// Same drawing as [`program`], with every loop drawn as a cluster inside the clusters
+/// of the loops containing it. Deeper loops get a different color and back edges are bold.
+pub fn loop_forests<W: Write>(ctxt: ProgramCtxt, writer: &mut BufWriter<W>) -> Result<()> {
+    writeln!(writer, "digraph {{")?;
+
+    for (index, (procedure, cf_graph)) in ctxt.program.procedures.iter().enumerate() {
+        let cluster_ctxt = Ctxt {
+            cf_graph,
+            ast: ctxt.ast,
+            library: ctxt.library,
+        };
+
+        let name = procedure_name(*procedure, ctxt.ast);
+        let prefix = procedure_prefix(*procedure, ctxt);
+        let forest = loops::find(cf_graph, &dominators::dominators(cf_graph));
+
+        writeln!(writer, "subgraph cluster_{index} {{")?;
+        writeln!(writer, "label=\"{name}\"")?;
+
+        for bb_id in cf_graph
+            .basic_block_ids()
+            .filter(|bb_id| forest.innermost(*bb_id).is_none())
+        {
+            graph_node(bb_id, &prefix, cluster_ctxt, writer)?;
+        }
+
+        for (loop_id, _) in forest.loops().filter(|(_, lp)| lp.parent.is_none()) {
+            graph_loop(loop_id, &forest, &prefix, cluster_ctxt, writer)?;
+        }
+
+        for bb_id in cf_graph.basic_block_ids() {
+            let edges: &Edges = cf_graph.get(bb_id);
+
+            for index in 0..edges.len() {
+                let edge_kind: EdgeKind = *edges.get(index);
+                let goes_to: BasicBlockId = *edges.get(index);
+
+                let label = edge_to_graphviz_label(edge_kind, cluster_ctxt);
+                let style = match forest.is_back_edge(bb_id, goes_to) {
+                    true => "bold",
+                    false => "solid",
+                };
+
+                writeln!(
+                    writer,
+                    "\"{prefix}{bb_id}\" -> \"{prefix}{goes_to}\" [label=\"{label}\", style=\"{style}\"]"
+                )?;
+            }
+        }
+
+        writeln!(writer, "}}")?;
     }
 
     writeln!(writer, "}}")?;
     writer.flush()
 }
 
+fn graph_loop<W: Write>(
+    loop_id: LoopId,
+    forest: &LoopForest,
+    prefix: &str,
+    ctxt: Ctxt,
+    writer: &mut BufWriter<W>,
+) -> Result<()> {
+    const COLORS: [&str; 4] = ["lightblue", "palegreen", "lightgoldenrod", "lightpink"];
+
+    let lp = forest.get(loop_id);
+    let color = COLORS[(lp.depth - 1) % COLORS.len()];
+
+    writeln!(writer, "subgraph \"cluster_{prefix}loop_{}\" {{", loop_id.index())?;
+    writeln!(writer, "label=\"loop {}, depth {}\"", lp.header, lp.depth)?;
+    writeln!(writer, "style=filled")?;
+    writeln!(writer, "fillcolor=\"{color}\"")?;
+
+    for bb_id in lp
+        .body
+        .iter()
+        .copied()
+        .filter(|bb_id| forest.innermost(*bb_id) == Some(loop_id))
+    {
+        graph_node(bb_id, prefix, ctxt, writer)?;
+    }
+
+    for (child, _) in forest
+        .loops()
+        .filter(|(_, child)| child.parent == Some(loop_id))
+    {
+        graph_loop(child, forest, prefix, ctxt, writer)?;
+    }
+
+    writeln!(writer, "}}")
+}
+
+/// Same drawing as [`program`], but every block shows its SSA form instead of the source code.
+pub fn ssa_program<W: Write>(ctxt: ProgramCtxt, writer: &mut BufWriter<W>) -> Result<()> {
+    let variables = variables::resolve(ctxt.ast);
+
+    writeln!(writer, "digraph {{")?;
+
+    for (index, (procedure, cf_graph)) in ctxt.program.procedures.iter().enumerate() {
+        let ssa_ctxt = ssa::Ctxt {
+            cf_graph,
+            variables: &variables,
+            ast: ctxt.ast,
+        };
+
+        let cluster_ctxt = Ctxt {
+            cf_graph,
+            ast: ctxt.ast,
+            library: ctxt.library,
+        };
+
+        let name = procedure_name(*procedure, ctxt.ast);
+        let prefix = procedure_prefix(*procedure, ctxt);
+        let ssa = ssa::build(*procedure, ssa_ctxt);
+
+        writeln!(writer, "subgraph cluster_{index} {{")?;
+        writeln!(writer, "label=\"{name}\"")?;
+
+        for bb_id in cf_graph.basic_block_ids() {
+            let lines = ssa.lines(bb_id, ssa_ctxt);
+            let label: String = lines.iter().map(|line| format!("{line}\\l")).collect();
+
+            write_node(&prefix, bb_id, label, "box", writer)?;
+            graph_edges(bb_id, &prefix, cluster_ctxt, writer)?;
+        }
+
+        writeln!(writer, "}}")?;
+    }
+
+    writeln!(writer, "}}")?;
+    writer.flush()
+}
+
+fn procedure_name(procedure: Procedure, ast: &Ast) -> &str {
+    match procedure {
+        Procedure::Main => "main",
+        Procedure::Function(function) => &ast[ast[function].name],
+    }
+}
+
+/// Block ids are only unique within a graph, so nodes are prefixed with their procedure.
+fn procedure_prefix(procedure: Procedure, ctxt: ProgramCtxt) -> String {
+    let index = ctxt
+        .program
+        .procedures
+        .iter()
+        .position(|(candidate, _)| *candidate == procedure)
+        .unwrap_or_default();
+
+    format!("{index}:")
+}
+
 fn expr_to_string(expr: ExprId, ctxt: Ctxt) -> String {
     let metadata = ctxt.ast.get(expr);
     let Source::File(id) = metadata.source else {
@@ -51,42 +300,40 @@ struct GraphNodeConfig {
     shape: String,
 }
 
-fn basic_block_stmt_to_graphviz_config(bb_stmt: BasicBlockValue, ctxt: Ctxt) -> GraphNodeConfig {
+fn basic_block_stmt_to_string(bb_stmt: BasicBlockValue, ctxt: Ctxt) -> String {
     match bb_stmt {
-        BasicBlockValue::EnterBlock(id) => GraphNodeConfig {
-            label: format!("_enter_block_{id}_"),
-            shape: "box".to_string(),
-        },
-
-        BasicBlockValue::LeaveBlock(id) => GraphNodeConfig {
-            label: format!("_leave_block_{id}_"),
-            shape: "box".to_string(),
-        },
-
-        BasicBlockValue::StmtExpr(inner) => GraphNodeConfig {
-            label: stmt_to_string(inner.stmt_id, ctxt),
-            shape: "box".to_string(),
-        },
-
-        BasicBlockValue::Declaration(inner) => GraphNodeConfig {
-            label: stmt_to_string(inner.stmt_id, ctxt),
-            shape: "box".to_string(),
-        },
-
-        BasicBlockValue::EntryPoint => GraphNodeConfig {
-            label: "".to_string(),
-            shape: "doublecircle".to_string(),
-        },
-
-        BasicBlockValue::EndPoint => GraphNodeConfig {
-            label: "".to_string(),
-            shape: "point".to_string(),
-        },
-
-        BasicBlockValue::Condition(expr) => GraphNodeConfig {
-            label: expr_to_string(expr.global_id(), ctxt),
-            shape: "diamond".to_string(),
-        },
+        BasicBlockValue::EnterBlock(id) => format!("_enter_block_{id}_"),
+        BasicBlockValue::LeaveBlock(id) => format!("_leave_block_{id}_"),
+        BasicBlockValue::StmtExpr(inner) => stmt_to_string(inner.stmt_id, ctxt),
+        BasicBlockValue::Declaration(inner) => stmt_to_string(inner.stmt_id, ctxt),
+    }
+}
+
+fn basic_block_to_graphviz_config(basic_block: &BasicBlock, ctxt: Ctxt) -> GraphNodeConfig {
+    let mut lines: Vec<_> = basic_block
+        .stmts
+        .iter()
+        .map(|stmt| basic_block_stmt_to_string(*stmt, ctxt))
+        .collect();
+
+    let shape = match basic_block.terminator {
+        Terminator::Exit => "point",
+        Terminator::Jump => "box",
+        Terminator::Branch(inner) => {
+            lines.push(format!("branch {}", expr_to_string(inner.inner.global_id(), ctxt)));
+            "box"
+        }
+    };
+
+    // Every line is left justified.
+    let label = lines
+        .iter()
+        .map(|line| format!("{}\\l", line.trim()))
+        .collect();
+
+    GraphNodeConfig {
+        label,
+        shape: shape.to_string(),
     }
 }
 
@@ -98,16 +345,33 @@ fn edge_to_graphviz_label(edge_kind: EdgeKind, _ctxt: Ctxt) -> String {
     }
 }
 
-fn graph_block<W: Write>(bb_id: BasicBlockId, ctxt: Ctxt, writer: &mut BufWriter<W>) -> Result<()> {
+fn graph_node<W: Write>(bb_id: BasicBlockId, prefix: &str, ctxt: Ctxt, writer: &mut BufWriter<W>) -> Result<()> {
     let basic_block: &BasicBlock = ctxt.cf_graph.get(bb_id);
-    let mut config = basic_block_stmt_to_graphviz_config(basic_block.stmt, ctxt);
+    let config = basic_block_to_graphviz_config(basic_block, ctxt);
 
-    config.label = config.label.replace('"', "\\\"");
-    config.label = config.label.replace('(', "\\(");
-    config.label = config.label.replace(')', "\\)");
+    write_node(prefix, bb_id, config.label, &config.shape, writer)
+}
+
+fn write_node<W: Write>(
+    prefix: &str,
+    bb_id: BasicBlockId,
+    mut label: String,
+    shape: &str,
+    writer: &mut BufWriter<W>,
+) -> Result<()> {
+    label = label.replace('"', "\\\"");
+    label = label.replace('(', "\\(");
+    label = label.replace(')', "\\)");
 
-    writeln!(writer, "\"{bb_id}\" [label=\"{}\", shape=\"{}\", center=true]", config.label, config.shape)?;
+    writeln!(writer, "\"{prefix}{bb_id}\" [label=\"{label}\", shape=\"{shape}\", center=true]")
+}
+
+fn graph_block<W: Write>(bb_id: BasicBlockId, prefix: &str, ctxt: Ctxt, writer: &mut BufWriter<W>) -> Result<()> {
+    graph_node(bb_id, prefix, ctxt, writer)?;
+    graph_edges(bb_id, prefix, ctxt, writer)
+}
 
+fn graph_edges<W: Write>(bb_id: BasicBlockId, prefix: &str, ctxt: Ctxt, writer: &mut BufWriter<W>) -> Result<()> {
     let edges: &Edges = ctxt.cf_graph.get(bb_id);
 
     for index in 0..edges.len() {
@@ -120,7 +384,7 @@ fn graph_block<W: Write>(bb_id: BasicBlockId, ctxt: Ctxt, writer: &mut BufWriter
         label = label.replace('(', "\\(");
         label = label.replace(')', "\\)");
 
-        writeln!(writer, "\"{bb_id}\" -> \"{goes_to}\" [label=\"{label}\"]")?;
+        writeln!(writer, "\"{prefix}{bb_id}\" -> \"{prefix}{goes_to}\" [label=\"{label}\"]")?;
     }
 
     Ok(())
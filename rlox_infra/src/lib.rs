@@ -1,3 +1,5 @@
+pub mod similar;
+
 /// rlox uses a data oriented pattern in several places, which means that
 /// dealing with structs of vectors. To ease such scenarios this trait allows
 /// client code to use the type system to specify Vec access.
@@ -0,0 +1,67 @@
+//! Closest names to a misspelled one, for "did you mean" suggestions.
+
+/// Edits needed to turn `a` into `b`, where an edit inserts, removes or replaces a
+/// character, or swaps two adjacent ones.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+
+    // Rows of the distances between the prefixes of `a` and every prefix of `b`.
+    let mut before_previous = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+
+        for j in 1..=b.len() {
+            let replace = previous[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            current[j] = replace.min(previous[j] + 1).min(current[j - 1] + 1);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1);
+            }
+        }
+
+        std::mem::swap(&mut before_previous, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// The candidate closest to `name`, if it is close enough to be a typo: a third of
+/// the characters of `name` can be wrong, at least one. Ties go to the first candidate.
+pub fn closest<'a, I: IntoIterator<Item = &'a str>>(name: &str, candidates: I) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swaps_are_a_single_edit() {
+        assert_eq!(edit_distance("whlie", "while"), 1);
+        assert_eq!(edit_distance("retrun", "return"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "var"), 3);
+    }
+
+    #[test]
+    fn only_close_names_are_suggested() {
+        let names = ["counter", "println", "read_file"];
+
+        assert_eq!(closest("countr", names), Some("counter"));
+        assert_eq!(closest("prinltn", names), Some("println"));
+        assert_eq!(closest("total", names), None);
+        assert_eq!(closest("counter", names), None);
+    }
+}
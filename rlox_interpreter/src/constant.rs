@@ -0,0 +1,119 @@
+//! Evaluation of expressions whose value is known before running the
+//! program, the ones built only from literals and operators. The result
+//! is always the value the interpreter would compute for them.
+
+use rlox_ast::Ast;
+use rlox_ast::expr::{BinaryOperator, Expr, ExprKind, UnaryOperator};
+
+use crate::value_system::{self, Value};
+
+/// `None` when the value depends on the execution, or when evaluating the expression fails.
+pub fn evaluate(expr: Expr, ast: &Ast) -> Option<Value> {
+    evaluate_with(expr, ast, &mut |_| None)
+}
+
+/// Same as [`evaluate`], but identifiers are constant when `variable` knows their value.
+pub fn evaluate_with<F>(expr: Expr, ast: &Ast, variable: &mut F) -> Option<Value>
+where
+    F: FnMut(Expr) -> Option<Value>,
+{
+    match expr.kind() {
+        ExprKind::Nil => Some(Value::Nil),
+        ExprKind::Boolean(inner) => Some(Value::Boolean(inner)),
+        ExprKind::Decimal(inner) => Some(Value::Decimal(inner)),
+        ExprKind::Natural(inner) => Some(Value::Natural(inner)),
+        ExprKind::String(inner) => Some(Value::String(ast[inner].into())),
+
+        ExprKind::Binary(inner) => {
+            let binary = &ast[inner];
+            let lhs = evaluate_with(binary.lhs, ast, variable)?;
+
+            // Same lazy evaluation as the interpreter, the right side does not need to be constant.
+            match (binary.operator, &lhs) {
+                (BinaryOperator::LogicAnd, Value::Boolean(false)) => return Some(Value::Boolean(false)),
+                (BinaryOperator::LogicOr, Value::Boolean(true)) => return Some(Value::Boolean(true)),
+                _ => (),
+            }
+
+            let rhs = evaluate_with(binary.rhs, ast, variable)?;
+
+            if panics(binary.operator, &lhs, &rhs) {
+                return None;
+            }
+
+            value_system::binary_operation(binary.operator, lhs, rhs).ok()
+        }
+
+        ExprKind::Unary(inner) => {
+            let unary = &ast[inner];
+            let operand = evaluate_with(unary.operand, ast, variable)?;
+
+            let overflows = match (unary.operator, &operand) {
+                (UnaryOperator::Minus, Value::Natural(value)) => *value as i64 == i64::MIN,
+                (UnaryOperator::Minus, Value::Signed(value)) => *value == i64::MIN,
+                _ => false,
+            };
+
+            if overflows {
+                return None;
+            }
+
+            value_system::unary_operation(unary.operator, operand).ok()
+        }
+
+        ExprKind::Identifier(_) => variable(expr),
+
+        ExprKind::Assign(_) | ExprKind::Call(_) => None,
+    }
+}
+
+/// Integer operations that abort the interpreter instead of producing a value.
+fn panics(operator: BinaryOperator, lhs: &Value, rhs: &Value) -> bool {
+    if !matches!(operator, BinaryOperator::Division | BinaryOperator::Modulus) {
+        return false;
+    }
+
+    match (lhs, rhs) {
+        (Value::Natural(_) | Value::Signed(_), Value::Natural(0) | Value::Signed(0)) => true,
+        (Value::Signed(i64::MIN), Value::Signed(-1)) => matches!(operator, BinaryOperator::Modulus),
+        (Value::Natural(lhs), Value::Signed(-1)) => {
+            *lhs as i64 == i64::MIN && matches!(operator, BinaryOperator::Modulus)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_ast::stmt::StmtKind;
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn evaluate_code(code: &str) -> Option<String> {
+        let code = format!("{code};");
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        let StmtKind::Expr(expr) = ast.main()[0].kind() else {
+            panic!("{code:?} should be an expression");
+        };
+
+        evaluate(expr, &ast).map(|value| value.to_string())
+    }
+
+    #[test_case("3 * 4 + 1", Some("13"); "arithmetic")]
+    #[test_case("-2 + 1.5", Some("-0.5"); "casts to common type")]
+    #[test_case("1 < 2 and !false", Some("true"); "logic operators")]
+    #[test_case("false and a", Some("false"); "lazy and")]
+    #[test_case("true and a", None; "variables are not constant")]
+    #[test_case("println(1)", None; "calls are not constant")]
+    #[test_case("1 / 0", None; "division by zero")]
+    #[test_case("1 + true", None; "operation not defined")]
+    fn constant_expressions(code: &str, expected: Option<&str>) {
+        assert_eq!(evaluate_code(code).as_deref(), expected);
+    }
+}
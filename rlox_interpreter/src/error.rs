@@ -26,9 +26,9 @@ impl From<RuntimeError> for Error {
 
 #[derive(Debug)]
 pub struct OperationNotDefined {
-    pub(crate) start: usize,
-    pub(crate) end: usize,
-    pub(crate) source: Source,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
 }
 
 impl From<OperationNotDefined> for RuntimeError {
@@ -38,6 +38,10 @@ impl From<OperationNotDefined> for RuntimeError {
 }
 
 impl Message for OperationNotDefined {
+    fn code(&self) -> &'static str {
+        "E0201"
+    }
+
     fn description(&self) -> String {
         "The operation is not defined".into()
     }
@@ -53,9 +57,12 @@ impl Message for OperationNotDefined {
 
 #[derive(Debug)]
 pub struct VarNotFound {
-    pub(crate) start: usize,
-    pub(crate) end: usize,
-    pub(crate) source: Source,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+    /// Name in scope close enough to be the one meant. It is only a guess, so it is
+    /// a help and not an edit: reading another variable changes the program.
+    pub suggestion: Option<String>,
 }
 
 impl From<VarNotFound> for RuntimeError {
@@ -65,6 +72,10 @@ impl From<VarNotFound> for RuntimeError {
 }
 
 impl Message for VarNotFound {
+    fn code(&self) -> &'static str {
+        "E0202"
+    }
+
     fn description(&self) -> String {
         "Variable not found".into()
     }
@@ -76,13 +87,19 @@ impl Message for VarNotFound {
             source: self.source,
         }
     }
+
+    fn help(&self) -> Option<String> {
+        self.suggestion
+            .as_ref()
+            .map(|name| format!("Maybe you meant `{name}`"))
+    }
 }
 
 #[derive(Debug)]
 pub struct InvalidAssign {
-    pub(crate) start: usize,
-    pub(crate) end: usize,
-    pub(crate) source: Source,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
 }
 
 impl From<InvalidAssign> for RuntimeError {
@@ -92,6 +109,10 @@ impl From<InvalidAssign> for RuntimeError {
 }
 
 impl Message for InvalidAssign {
+    fn code(&self) -> &'static str {
+        "E0203"
+    }
+
     fn description(&self) -> String {
         "Assignments need a memory location".to_string()
     }
@@ -107,10 +128,10 @@ impl Message for InvalidAssign {
 
 #[derive(Debug)]
 pub struct UnexpectedValue {
-    pub(crate) start: usize,
-    pub(crate) end: usize,
-    pub(crate) source: Source,
-    pub(crate) found: Value,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+    pub found: Value,
 }
 
 impl From<UnexpectedValue> for RuntimeError {
@@ -120,6 +141,10 @@ impl From<UnexpectedValue> for RuntimeError {
 }
 
 impl Message for UnexpectedValue {
+    fn code(&self) -> &'static str {
+        "E0204"
+    }
+
     fn description(&self) -> String {
         format!("Found: {}", self.found)
     }
@@ -135,11 +160,11 @@ impl Message for UnexpectedValue {
 
 #[derive(Debug)]
 pub struct WrongNumberOfArgs {
-    pub(crate) start: usize,
-    pub(crate) end: usize,
-    pub(crate) source: Source,
-    pub(crate) got: usize,
-    pub(crate) expect: usize,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+    pub got: usize,
+    pub expect: usize,
 }
 
 impl From<WrongNumberOfArgs> for RuntimeError {
@@ -149,6 +174,10 @@ impl From<WrongNumberOfArgs> for RuntimeError {
 }
 
 impl Message for WrongNumberOfArgs {
+    fn code(&self) -> &'static str {
+        "E0205"
+    }
+
     fn description(&self) -> String {
         format!("Expected {} arguments, but got {}", self.expect, self.got)
     }
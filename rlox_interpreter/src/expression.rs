@@ -1,12 +1,13 @@
 use rlox_ast::expr::*;
 use rlox_ast::{Ast, Identifier};
 use rlox_infra::StructVec;
+use rlox_infra::similar;
 
 use crate::RuntimeResult;
 use crate::error;
 use crate::native_functions::NativeFnContext;
 use crate::runtime::Runtime;
-use crate::value_system::{self, Value, VsResult};
+use crate::value_system::{self, Value};
 
 pub fn deref_expression(expr: Expr, ast: &Ast, runtime: &mut Runtime) -> RuntimeResult<Value> {
     match expression(expr, ast, runtime)? {
@@ -52,13 +53,16 @@ fn assign(node: ExprNode<AssignId>, ast: &Ast, runtime: &mut Runtime) -> Runtime
 }
 
 fn identifier(node: ExprNode<Identifier>, ast: &Ast, runtime: &mut Runtime) -> RuntimeResult<Value> {
-    let Some(value) = runtime.address(&ast[node.inner]) else {
+    let name = &ast[node.inner];
+
+    let Some(value) = runtime.address(name) else {
         let metadata = ast.get(node.expr_id);
 
         return Err(From::from(error::VarNotFound {
             start: metadata.start,
             end: metadata.end,
             source: metadata.source,
+            suggestion: similar::closest(name, runtime.names()).map(String::from),
         }));
     };
 
@@ -79,7 +83,7 @@ fn binary(node: ExprNode<BinaryId>, ast: &Ast, runtime: &mut Runtime) -> Runtime
 
     let rhs = deref_expression(binary.rhs, ast, runtime)?;
 
-    let Ok(result) = apply_binary_operator(binary.operator, lhs, rhs) else {
+    let Ok(result) = value_system::binary_operation(binary.operator, lhs, rhs) else {
         let metadata = *ast.get(node.expr_id);
         return Err(From::from(error::OperationNotDefined {
             start: metadata.start,
@@ -91,37 +95,12 @@ fn binary(node: ExprNode<BinaryId>, ast: &Ast, runtime: &mut Runtime) -> Runtime
     Ok(result)
 }
 
-fn apply_binary_operator(operator: BinaryOperator, lhs: Value, rhs: Value) -> VsResult<Value> {
-    match operator {
-        BinaryOperator::Division => value_system::div(lhs, rhs),
-        BinaryOperator::Equal => value_system::equal(lhs, rhs),
-        BinaryOperator::Greater => value_system::greater(lhs, rhs),
-        BinaryOperator::GreaterOrEqual => value_system::greater_or_equal(lhs, rhs),
-        BinaryOperator::Less => value_system::less(lhs, rhs),
-        BinaryOperator::LessOrEqual => value_system::less_or_equal(lhs, rhs),
-        BinaryOperator::LogicAnd => value_system::and(lhs, rhs),
-        BinaryOperator::LogicOr => value_system::or(lhs, rhs),
-        BinaryOperator::Minus => value_system::sub(lhs, rhs),
-        BinaryOperator::Modulus => value_system::modulus(lhs, rhs),
-        BinaryOperator::Multiply => value_system::mul(lhs, rhs),
-        BinaryOperator::NotEqual => value_system::not_equal(lhs, rhs),
-        BinaryOperator::Plus => value_system::add(lhs, rhs),
-    }
-}
-
-fn apply_unary_operator(operator: UnaryOperator, operand: Value) -> VsResult<Value> {
-    match operator {
-        UnaryOperator::Negation => value_system::not(operand),
-        UnaryOperator::Minus => value_system::neg(operand),
-    }
-}
-
 fn unary(node: ExprNode<UnaryId>, ast: &Ast, runtime: &mut Runtime) -> RuntimeResult<Value> {
     let unary = &ast[node.inner];
 
     let operand = deref_expression(unary.operand, ast, runtime)?;
 
-    let Ok(result) = apply_unary_operator(unary.operator, operand) else {
+    let Ok(result) = value_system::unary_operation(unary.operator, operand) else {
         let metadata = *ast.get(node.expr_id);
 
         return Err(From::from(error::OperationNotDefined {
@@ -150,14 +129,13 @@ fn call(node: ExprNode<CallId>, ast: &Ast, runtime: &mut Runtime) -> RuntimeResu
     };
 
     let mut context = NativeFnContext {
-        ast,
         args: Vec::with_capacity(call.arguments.len()),
-        caller: node.expr_id,
+        caller: *ast.get(node.expr_id),
     };
 
     for arg in call.arguments.iter().copied() {
         context.args.push(deref_expression(arg, ast, runtime)?);
     }
 
-    (lhs.function)(context, runtime)
+    (lhs.function)(context)
 }
@@ -1,17 +1,19 @@
+pub mod constant;
+pub mod error;
+pub mod native_functions;
 pub mod value_system;
 
-mod error;
 mod expression;
-mod native_functions;
 mod runtime;
 mod statement;
 
 pub use value_system::Value;
 
 use rlox_ast::Ast;
+use rlox_errors::Diagnostics;
 use runtime::Runtime;
 
-type RuntimeResult<T> = Result<T, error::RuntimeError>;
+pub type RuntimeResult<T> = Result<T, error::RuntimeError>;
 
 #[derive(Debug, Clone, Copy)]
 pub struct RuntimeFailure;
@@ -19,12 +21,13 @@ pub struct RuntimeFailure;
 #[derive(Debug, Clone, Copy)]
 pub struct EvalReport;
 
-pub fn eval(ast: &Ast) -> Result<EvalReport, RuntimeFailure> {
+/// Runs the program, the error that stops it goes to `diagnostics`.
+pub fn eval(ast: &Ast, diagnostics: &mut Diagnostics) -> Result<EvalReport, RuntimeFailure> {
     let mut runtime = Runtime::new();
 
     for stmt in ast.main().iter().copied() {
         if let Err(error) = statement::eval(stmt, ast, &mut runtime) {
-            rlox_errors::error(error);
+            diagnostics.error(error);
             return Err(RuntimeFailure);
         }
     }
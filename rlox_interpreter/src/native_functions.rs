@@ -1,13 +1,10 @@
 use std::fs;
 
-use rlox_ast::Ast;
-use rlox_ast::expr::ExprId;
 use rlox_errors::compiler_log;
-use rlox_infra::StructVec;
+use rlox_source::SourceMetadata;
 
 use crate::RuntimeResult;
 use crate::error;
-use crate::runtime::Runtime;
 use crate::value_system::Value;
 
 #[rustfmt::skip]
@@ -19,13 +16,14 @@ pub const REGISTRY: &[NativeFn] = &[
 #[derive(Copy, Clone, Debug)]
 pub struct NativeFn {
     pub name: &'static str,
-    pub function: fn(NativeFnContext, &Runtime) -> RuntimeResult<Value>,
+    pub function: fn(NativeFnContext) -> RuntimeResult<Value>,
 }
 
-pub struct NativeFnContext<'a> {
+/// Native functions are shared by every backend, so the call site is
+/// described by its metadata instead of by an AST node.
+pub struct NativeFnContext {
     pub args: Vec<Value>,
-    pub caller: ExprId,
-    pub ast: &'a Ast,
+    pub caller: SourceMetadata,
 }
 
 const READ_FILE: NativeFn = NativeFn {
@@ -33,9 +31,9 @@ const READ_FILE: NativeFn = NativeFn {
     function: read_file_to_string,
 };
 
-pub fn read_file_to_string(context: NativeFnContext, _runtime: &Runtime) -> RuntimeResult<Value> {
+pub fn read_file_to_string(context: NativeFnContext) -> RuntimeResult<Value> {
     if context.args.len() != 1 {
-        let metadata = context.ast.get(context.caller);
+        let metadata = context.caller;
 
         return Err(From::from(error::WrongNumberOfArgs {
             start: metadata.start,
@@ -49,7 +47,7 @@ pub fn read_file_to_string(context: NativeFnContext, _runtime: &Runtime) -> Runt
     let file_path = &context.args[0];
 
     let Value::String(file_path) = file_path else {
-        let metadata = context.ast.get(context.caller);
+        let metadata = context.caller;
 
         return Err(From::from(error::UnexpectedValue {
             start: metadata.start,
@@ -71,9 +69,9 @@ const PRINTLN: NativeFn = NativeFn {
     function: println,
 };
 
-pub fn println(context: NativeFnContext, _runtime: &Runtime) -> RuntimeResult<Value> {
+pub fn println(context: NativeFnContext) -> RuntimeResult<Value> {
     if context.args.len() != 1 {
-        let metadata = context.ast.get(context.caller);
+        let metadata = context.caller;
 
         return Err(From::from(error::WrongNumberOfArgs {
             start: metadata.start,
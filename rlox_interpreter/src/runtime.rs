@@ -60,6 +60,13 @@ impl<'a> Runtime<'a> {
         None
     }
 
+    /// Every name in scope, native functions included.
+    pub fn names(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.var_env
+            .iter()
+            .flat_map(|env| env.inner.keys().copied())
+    }
+
     pub fn insert(&mut self, id: &'a str, value: Value) -> MemAddr {
         if self.free_address == self.memory.len() {
             self.memory.extend((0..MEMORY_SIZE).map(|_| Value::Nil));
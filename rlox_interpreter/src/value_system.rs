@@ -1,4 +1,6 @@
-use crate::native_functions::NativeFn;
+use rlox_ast::expr::{BinaryOperator, UnaryOperator};
+
+use crate::native_functions::{self, NativeFn};
 use crate::runtime::MemAddr;
 
 pub type VsResult<T> = Result<T, OperationNotDefined>;
@@ -33,6 +35,65 @@ impl std::fmt::Display for Value {
 #[derive(Debug, Clone, Copy)]
 pub struct OperationNotDefined;
 
+/// What a value is, without the value itself. Addresses are not included, they
+/// are always dereferenced before operating on them.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    Nil,
+    Boolean,
+    Natural,
+    Signed,
+    Decimal,
+    String,
+    Fn,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 7] =
+        [Kind::Nil, Kind::Boolean, Kind::Natural, Kind::Signed, Kind::Decimal, Kind::String, Kind::Fn];
+
+    pub fn of(value: &Value) -> Option<Kind> {
+        match value {
+            Value::Nil => Some(Kind::Nil),
+            Value::Boolean(_) => Some(Kind::Boolean),
+            Value::Natural(_) => Some(Kind::Natural),
+            Value::Signed(_) => Some(Kind::Signed),
+            Value::Decimal(_) => Some(Kind::Decimal),
+            Value::String(_) => Some(Kind::String),
+            Value::Fn(_) => Some(Kind::Fn),
+            Value::Addr(_) => None,
+        }
+    }
+
+    /// Whether an operation is defined only depends on the kinds of its operands, so any
+    /// value works to find it out. Numbers are one to stay away from divisions by zero.
+    fn sample(self) -> Value {
+        match self {
+            Kind::Nil => Value::Nil,
+            Kind::Boolean => Value::Boolean(true),
+            Kind::Natural => Value::Natural(1),
+            Kind::Signed => Value::Signed(1),
+            Kind::Decimal => Value::Decimal(1.0),
+            Kind::String => Value::String(String::new()),
+            Kind::Fn => Value::Fn(native_functions::REGISTRY[0]),
+        }
+    }
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Kind::Nil => "nil".fmt(f),
+            Kind::Boolean => "boolean".fmt(f),
+            Kind::Natural => "natural".fmt(f),
+            Kind::Signed => "signed".fmt(f),
+            Kind::Decimal => "decimal".fmt(f),
+            Kind::String => "string".fmt(f),
+            Kind::Fn => "function".fmt(f),
+        }
+    }
+}
+
 fn cast_to_common(lhs: Value, rhs: Value) -> VsResult<(Value, Value)> {
     match (&lhs, &rhs) {
         (Value::Nil, _) => Ok((lhs, rhs)),
@@ -214,3 +275,65 @@ pub fn neg(value: Value) -> VsResult<Value> {
         _ => Err(OperationNotDefined),
     }
 }
+
+pub fn binary_operation(operator: BinaryOperator, lhs: Value, rhs: Value) -> VsResult<Value> {
+    match operator {
+        BinaryOperator::Division => div(lhs, rhs),
+        BinaryOperator::Equal => equal(lhs, rhs),
+        BinaryOperator::Greater => greater(lhs, rhs),
+        BinaryOperator::GreaterOrEqual => greater_or_equal(lhs, rhs),
+        BinaryOperator::Less => less(lhs, rhs),
+        BinaryOperator::LessOrEqual => less_or_equal(lhs, rhs),
+        BinaryOperator::LogicAnd => and(lhs, rhs),
+        BinaryOperator::LogicOr => or(lhs, rhs),
+        BinaryOperator::Minus => sub(lhs, rhs),
+        BinaryOperator::Modulus => modulus(lhs, rhs),
+        BinaryOperator::Multiply => mul(lhs, rhs),
+        BinaryOperator::NotEqual => not_equal(lhs, rhs),
+        BinaryOperator::Plus => add(lhs, rhs),
+    }
+}
+
+pub fn unary_operation(operator: UnaryOperator, operand: Value) -> VsResult<Value> {
+    match operator {
+        UnaryOperator::Negation => not(operand),
+        UnaryOperator::Minus => neg(operand),
+    }
+}
+
+/// Kind of the result of a binary operation, following the same rules as [`binary_operation`].
+pub fn binary_operation_kind(operator: BinaryOperator, lhs: Kind, rhs: Kind) -> VsResult<Kind> {
+    let result = binary_operation(operator, lhs.sample(), rhs.sample())?;
+    Ok(Kind::of(&result).expect("operations never produce addresses"))
+}
+
+/// Kind of the result of a unary operation, following the same rules as [`unary_operation`].
+pub fn unary_operation_kind(operator: UnaryOperator, operand: Kind) -> VsResult<Kind> {
+    let result = unary_operation(operator, operand.sample())?;
+    Ok(Kind::of(&result).expect("operations never produce addresses"))
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(BinaryOperator::Plus, Kind::Natural, Kind::Signed => Some(Kind::Signed); "naturals are cast to signed")]
+    #[test_case(BinaryOperator::Minus, Kind::Signed, Kind::Decimal => Some(Kind::Decimal); "integers are cast to decimal")]
+    #[test_case(BinaryOperator::Multiply, Kind::Nil, Kind::Natural => Some(Kind::Nil); "nil absorbs arithmetic")]
+    #[test_case(BinaryOperator::Less, Kind::Natural, Kind::Decimal => Some(Kind::Boolean); "comparisons are booleans")]
+    #[test_case(BinaryOperator::Division, Kind::Natural, Kind::Natural => Some(Kind::Natural); "division does not divide by zero")]
+    #[test_case(BinaryOperator::Plus, Kind::String, Kind::Natural => None; "strings do not mix with numbers")]
+    #[test_case(BinaryOperator::Equal, Kind::Fn, Kind::Fn => None; "functions can not be compared")]
+    #[test_case(BinaryOperator::LogicAnd, Kind::Boolean, Kind::Natural => None; "logic needs booleans")]
+    fn binary_kinds(operator: BinaryOperator, lhs: Kind, rhs: Kind) -> Option<Kind> {
+        binary_operation_kind(operator, lhs, rhs).ok()
+    }
+
+    #[test_case(UnaryOperator::Minus, Kind::Natural => Some(Kind::Signed); "negated naturals are signed")]
+    #[test_case(UnaryOperator::Negation, Kind::Natural => None; "only booleans are negated")]
+    fn unary_kinds(operator: UnaryOperator, operand: Kind) -> Option<Kind> {
+        unary_operation_kind(operator, operand).ok()
+    }
+}
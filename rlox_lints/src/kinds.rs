@@ -0,0 +1,437 @@
+//! Abstract interpretation over the kinds of values. Every variable holds the set
+//! of kinds it may have, and expressions are evaluated over those sets with the
+//! operator rules of the value system. Operations that fail for every kind are
+//! reported as certain failures, the ones that fail for some as possible ones.
+//! Nothing is reported when a value could be anything, like a parameter.
+
+use rlox_ast::Ast;
+use rlox_ast::expr::{BinaryOperator, Expr, ExprKind};
+use rlox_cf_graph::dataflow::{self, Analysis, Direction, Lattice, Point};
+use rlox_cf_graph::variables::Variables;
+use rlox_cf_graph::{
+    BasicBlock, BasicBlockId, BasicBlockValue, ControlFlowGraph, Procedure, Program, Terminator, reachability,
+};
+use rlox_infra::StructVec;
+use rlox_interpreter::native_functions;
+use rlox_interpreter::value_system::{self, Kind};
+use rlox_interpreter::{Value, constant};
+use rlox_source::SourceMetadata;
+
+use crate::warning::{LintWarning, NonBooleanCondition, NotCallable, UndefinedOperation};
+
+pub fn check(program: &Program, variables: &Variables, ast: &Ast, warnings: &mut Vec<LintWarning>) {
+    for (procedure, cf_graph) in program.procedures.iter() {
+        let analysis = KindAnalysis {
+            procedure: *procedure,
+            cf_graph,
+            variables,
+            ast,
+        };
+
+        let facts = dataflow::solve(&analysis, cf_graph);
+        let reachable = reachability::reachable(cf_graph, |node| match constant::evaluate(node.inner, ast) {
+            Some(Value::Boolean(value)) => Some(value),
+            _ => None,
+        });
+
+        for id in cf_graph.basic_block_ids().filter(|id| reachable[*id]) {
+            let mut environment = facts.get(Point::In(id)).clone();
+            analysis.run(id, &mut environment, warnings);
+        }
+    }
+}
+
+/// Kinds a value may have. The empty set means the value is never computed, and
+/// every kind at once that nothing is known about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Kinds {
+    bits: u8,
+}
+
+impl Kinds {
+    const NONE: Kinds = Kinds {
+        bits: 0,
+    };
+
+    const UNKNOWN: Kinds = Kinds {
+        bits: (1 << Kind::ALL.len()) - 1,
+    };
+
+    fn single(kind: Kind) -> Kinds {
+        Kinds {
+            bits: 1 << kind as u8,
+        }
+    }
+
+    fn contains(self, kind: Kind) -> bool {
+        self.bits & Kinds::single(kind).bits != 0
+    }
+
+    fn insert(&mut self, kind: Kind) {
+        self.bits |= Kinds::single(kind).bits;
+    }
+
+    fn union(self, other: Kinds) -> Kinds {
+        Kinds {
+            bits: self.bits | other.bits,
+        }
+    }
+
+    fn is_empty(self) -> bool {
+        self == Kinds::NONE
+    }
+
+    fn is_unknown(self) -> bool {
+        self == Kinds::UNKNOWN
+    }
+
+    fn iter(self) -> impl Iterator<Item = Kind> {
+        Kind::ALL
+            .into_iter()
+            .filter(move |kind| self.contains(*kind))
+    }
+}
+
+/// Kinds of every variable, indexed by their [`VarId`](rlox_cf_graph::variables::VarId).
+#[derive(Debug, Clone, PartialEq)]
+struct Environment {
+    kinds: Vec<Kinds>,
+}
+
+impl Lattice for Environment {
+    fn join(&mut self, other: &Self) {
+        for (kinds, other) in self.kinds.iter_mut().zip(other.kinds.iter()) {
+            *kinds = kinds.union(*other);
+        }
+    }
+}
+
+struct KindAnalysis<'a> {
+    procedure: Procedure,
+    cf_graph: &'a ControlFlowGraph,
+    variables: &'a Variables,
+    ast: &'a Ast,
+}
+
+impl Analysis for KindAnalysis<'_> {
+    type Fact = Environment;
+
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn bottom(&self) -> Environment {
+        Environment {
+            kinds: vec![Kinds::NONE; self.variables.len()],
+        }
+    }
+
+    fn boundary(&self) -> Environment {
+        let mut environment = self.bottom();
+
+        if let Procedure::Function(function) = self.procedure {
+            for param in self.variables.params(function).iter() {
+                environment.kinds[param.index()] = Kinds::UNKNOWN;
+            }
+        }
+
+        environment
+    }
+
+    fn transfer(&self, id: BasicBlockId, input: &Environment) -> Environment {
+        let mut environment = input.clone();
+        self.run(id, &mut environment, &mut Vec::new());
+
+        environment
+    }
+}
+
+impl KindAnalysis<'_> {
+    /// Evaluates the block over the kinds, failures are only reported while checking.
+    fn run(&self, id: BasicBlockId, environment: &mut Environment, warnings: &mut Vec<LintWarning>) {
+        let basic_block: &BasicBlock = self.cf_graph.get(id);
+
+        for stmt in basic_block.stmts.iter() {
+            match stmt {
+                BasicBlockValue::Declaration(node) => {
+                    let kinds = match self.ast[node.inner].value {
+                        Some(value) => self.expr(value, environment, warnings),
+                        None => Kinds::single(Kind::Nil),
+                    };
+
+                    if let Some(var) = self.variables.declaration(node.stmt_id) {
+                        environment.kinds[var.index()] = kinds;
+                    }
+                }
+
+                BasicBlockValue::StmtExpr(node) => {
+                    self.expr(node.inner, environment, warnings);
+                }
+
+                BasicBlockValue::EnterBlock(_) | BasicBlockValue::LeaveBlock(_) => (),
+            }
+        }
+
+        if let Terminator::Branch(node) = basic_block.terminator {
+            let kinds = self.expr(node.inner, environment, warnings);
+
+            if let Some(certain) = failure(kinds, kinds.contains(Kind::Boolean), kinds.is_unknown()) {
+                let metadata = *self.ast.get(node.inner.global_id());
+
+                warnings.push(
+                    NonBooleanCondition {
+                        certain,
+                        found: kinds.iter().filter(|kind| *kind != Kind::Boolean).collect(),
+                        start: metadata.start,
+                        end: metadata.end,
+                        source: metadata.source,
+                    }
+                    .into(),
+                );
+            }
+        }
+    }
+
+    fn expr(&self, expr: Expr, environment: &mut Environment, warnings: &mut Vec<LintWarning>) -> Kinds {
+        match expr.kind() {
+            ExprKind::Nil => Kinds::single(Kind::Nil),
+            ExprKind::Boolean(_) => Kinds::single(Kind::Boolean),
+            ExprKind::Natural(_) => Kinds::single(Kind::Natural),
+            ExprKind::Decimal(_) => Kinds::single(Kind::Decimal),
+            ExprKind::String(_) => Kinds::single(Kind::String),
+
+            ExprKind::Identifier(inner) => match self.variables.variable(expr.global_id()) {
+                Some(var) => environment.kinds[var.index()],
+                None => {
+                    let name = &self.ast[inner];
+                    let native = native_functions::REGISTRY
+                        .iter()
+                        .any(|native_fn| native_fn.name == name);
+
+                    match native {
+                        true => Kinds::single(Kind::Fn),
+                        false => Kinds::UNKNOWN,
+                    }
+                }
+            },
+
+            ExprKind::Assign(inner) => {
+                let assign = &self.ast[inner];
+                let kinds = self.expr(assign.rhs, environment, warnings);
+
+                match self.variables.variable(assign.lhs.global_id()) {
+                    Some(var) => environment.kinds[var.index()] = kinds,
+                    None => {
+                        self.expr(assign.lhs, environment, warnings);
+                    }
+                }
+
+                Kinds::single(Kind::Nil)
+            }
+
+            ExprKind::Binary(inner) => {
+                let binary = &self.ast[inner];
+                let lhs = self.expr(binary.lhs, environment, warnings);
+
+                let short_circuit = matches!(
+                    (binary.operator, constant::evaluate(binary.lhs, self.ast)),
+                    (BinaryOperator::LogicAnd, Some(Value::Boolean(false)))
+                        | (BinaryOperator::LogicOr, Some(Value::Boolean(true)))
+                );
+
+                if short_circuit {
+                    return Kinds::single(Kind::Boolean);
+                }
+
+                // The right side may not run, in that case the left side is a boolean and the result too.
+                let lazy = matches!(binary.operator, BinaryOperator::LogicAnd | BinaryOperator::LogicOr)
+                    && lhs.contains(Kind::Boolean);
+
+                let skipped = lazy.then(|| environment.clone());
+                let rhs = self.expr(binary.rhs, environment, warnings);
+
+                if let Some(skipped) = skipped {
+                    environment.join(&skipped);
+                }
+
+                let mut result = Kinds::NONE;
+                let mut undefined = None;
+
+                if lazy {
+                    result.insert(Kind::Boolean);
+                }
+
+                for lhs_kind in lhs.iter() {
+                    for rhs_kind in rhs.iter() {
+                        match value_system::binary_operation_kind(binary.operator, lhs_kind, rhs_kind) {
+                            Ok(kind) => result.insert(kind),
+                            Err(_) => {
+                                undefined.get_or_insert(vec![lhs_kind, rhs_kind]);
+                            }
+                        }
+                    }
+                }
+
+                let unknown = lhs.is_unknown() || rhs.is_unknown();
+                self.undefined_operation(expr, undefined, !result.is_empty(), unknown, warnings);
+
+                result
+            }
+
+            ExprKind::Unary(inner) => {
+                let unary = &self.ast[inner];
+                let operand = self.expr(unary.operand, environment, warnings);
+
+                let mut result = Kinds::NONE;
+                let mut undefined = None;
+
+                for kind in operand.iter() {
+                    match value_system::unary_operation_kind(unary.operator, kind) {
+                        Ok(kind) => result.insert(kind),
+                        Err(_) => {
+                            undefined.get_or_insert(vec![kind]);
+                        }
+                    }
+                }
+
+                self.undefined_operation(expr, undefined, !result.is_empty(), operand.is_unknown(), warnings);
+
+                result
+            }
+
+            ExprKind::Call(inner) => {
+                let call = &self.ast[inner];
+                let callee = self.expr(call.lhs, environment, warnings);
+
+                for argument in call.arguments.iter().copied() {
+                    self.expr(argument, environment, warnings);
+                }
+
+                if let Some(certain) = failure(callee, callee.contains(Kind::Fn), callee.is_unknown()) {
+                    let metadata = *self.ast.get(call.lhs.global_id());
+
+                    warnings.push(
+                        NotCallable {
+                            certain,
+                            found: callee.iter().filter(|kind| *kind != Kind::Fn).collect(),
+                            start: metadata.start,
+                            end: metadata.end,
+                            source: metadata.source,
+                        }
+                        .into(),
+                    );
+                }
+
+                // Native functions return different kinds of values.
+                match callee.contains(Kind::Fn) {
+                    true => Kinds::UNKNOWN,
+                    false => Kinds::NONE,
+                }
+            }
+        }
+    }
+
+    fn undefined_operation(
+        &self,
+        expr: Expr,
+        undefined: Option<Vec<Kind>>,
+        defined: bool,
+        unknown: bool,
+        warnings: &mut Vec<LintWarning>,
+    ) {
+        let Some(found) = undefined else {
+            return;
+        };
+
+        if defined && unknown {
+            return;
+        }
+
+        let metadata: SourceMetadata = *self.ast.get(expr.global_id());
+
+        warnings.push(
+            UndefinedOperation {
+                certain: !defined,
+                found,
+                start: metadata.start,
+                end: metadata.end,
+                source: metadata.source,
+            }
+            .into(),
+        );
+    }
+}
+
+/// Whether a value that must have some kind never has it, `Some(true)`, or may not have it, `Some(false)`.
+fn failure(kinds: Kinds, expected: bool, unknown: bool) -> Option<bool> {
+    if kinds.is_empty() {
+        return None;
+    }
+
+    match (expected, kinds.bits.count_ones() > 1 && !unknown) {
+        (false, _) => Some(true),
+        (true, true) => Some(false),
+        (true, false) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use crate::lint;
+    use crate::warning::LintWarning;
+
+    fn warnings(code: &str) -> Vec<String> {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        lint(&ast)
+            .into_iter()
+            .filter(|warning| {
+                matches!(
+                    warning,
+                    LintWarning::UndefinedOperation(_)
+                        | LintWarning::NonBooleanCondition(_)
+                        | LintWarning::NotCallable(_)
+                )
+            })
+            .map(|warning| {
+                let metadata = warning.source_metadata();
+                let span = code[metadata.start..metadata.end].trim();
+
+                format!("{}: {span}", warning.description())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn operations_that_always_fail_are_reported() {
+        let found = warnings("var a = \"text\"; println(a + 1); var b = !2;");
+        assert_eq!(found, [
+            "This operation always fails, it is not defined for string and natural: a + 1",
+            "This operation always fails, it is not defined for natural: !2",
+        ]);
+    }
+
+    #[test]
+    fn operations_that_fail_on_some_paths_are_reported() {
+        let found = warnings("var a = 1; if a > 0 { a = \"text\"; } println(a * 2);");
+        assert_eq!(found, ["This operation may fail, it is not defined for string and natural: a * 2"]);
+    }
+
+    #[test]
+    fn conditions_and_callees_are_checked() {
+        let found = warnings("var a; if a { println(1); } var b = 1; b(2); while 1 < 2 and 3 { println(4); }");
+        assert_eq!(found, [
+            "This condition is never a boolean, it can be nil: a",
+            "This is never a function, it can be natural: b",
+            "This operation may fail, it is not defined for boolean and natural: 1 < 2 and 3",
+        ]);
+    }
+
+    #[test]
+    fn nil_arithmetic_and_lazy_logic_are_fine() {
+        assert!(warnings("var a; println(a + 1); var b = false and 1; println(b);").is_empty());
+    }
+}
@@ -0,0 +1,40 @@
+//! Static checks over the control-flow graph of a program. They never stop
+//! the compilation, everything they find is reported as a warning.
+
+pub mod warning;
+
+mod kinds;
+mod uninitialized;
+mod unreachable;
+mod unused;
+
+use rlox_ast::Ast;
+use rlox_cf_graph::{build_cfg, variables};
+use rlox_errors::Diagnostics;
+use warning::LintWarning;
+
+/// Runs every lint, the warnings are sorted by their position in the source code.
+pub fn lint(ast: &Ast) -> Vec<LintWarning> {
+    let program = build_cfg::from_program(ast);
+    let variables = variables::resolve(ast);
+
+    let mut warnings = Vec::new();
+    unused::check(&program, &variables, ast, &mut warnings);
+    uninitialized::check(&program, &variables, ast, &mut warnings);
+    unreachable::check(&program, ast, &mut warnings);
+    kinds::check(&program, &variables, ast, &mut warnings);
+
+    warnings.sort_by_key(|warning| {
+        let metadata = warning.source_metadata();
+        (metadata.source, metadata.start)
+    });
+
+    warnings
+}
+
+/// Same as [`lint`], but the warnings go to `diagnostics`.
+pub fn check(ast: &Ast, diagnostics: &mut Diagnostics) {
+    for warning in lint(ast) {
+        diagnostics.warning(warning);
+    }
+}
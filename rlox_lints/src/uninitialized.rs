@@ -0,0 +1,108 @@
+use rlox_ast::Ast;
+use rlox_cf_graph::Program;
+use rlox_cf_graph::reaching_definitions::ReachingDefinitions;
+use rlox_cf_graph::variables::{Declarator, Definition, VarId, Variables};
+use rlox_infra::StructVec;
+use rlox_source::SourceMetadata;
+
+use crate::warning::{LintWarning, MaybeUninitializedRead, UninitializedRead};
+
+/// Reads reached by the nil that `var a;` stores implicitly, either from every path or from some of them.
+pub fn check(program: &Program, variables: &Variables, ast: &Ast, warnings: &mut Vec<LintWarning>) {
+    for (procedure, cf_graph) in program.procedures.iter() {
+        let reaching_definitions = ReachingDefinitions {
+            procedure: *procedure,
+            cf_graph,
+            variables,
+            ast,
+        };
+
+        for read in reaching_definitions.uses() {
+            let implicit_nil = read
+                .definitions
+                .iter()
+                .filter(|definition| is_implicit_nil(**definition, read.var, variables, ast))
+                .count();
+
+            if implicit_nil == 0 {
+                continue;
+            }
+
+            let Declarator::Declaration(declaration) = variables.declarator(read.var) else {
+                continue;
+            };
+
+            let name = variables.name(read.var, ast).to_string();
+            let metadata: SourceMetadata = *ast.get(read.expr);
+            let declaration = *ast.get(declaration.stmt_id);
+
+            let warning = match implicit_nil == read.definitions.len() {
+                true => UninitializedRead {
+                    name,
+                    start: metadata.start,
+                    end: metadata.end,
+                    source: metadata.source,
+                    declaration,
+                }
+                .into(),
+
+                false => MaybeUninitializedRead {
+                    name,
+                    start: metadata.start,
+                    end: metadata.end,
+                    source: metadata.source,
+                    declaration,
+                }
+                .into(),
+            };
+
+            warnings.push(warning);
+        }
+    }
+}
+
+fn is_implicit_nil(definition: Definition, var: VarId, variables: &Variables, ast: &Ast) -> bool {
+    let Definition::Declaration(_) = definition else {
+        return false;
+    };
+
+    match variables.declarator(var) {
+        Declarator::Declaration(node) => ast[node.inner].value.is_none(),
+        Declarator::Param(..) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use crate::lint;
+    use crate::warning::LintWarning;
+
+    fn warnings(code: &str) -> Vec<LintWarning> {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        lint(&ast)
+    }
+
+    #[test]
+    fn reads_without_assignment_are_reported() {
+        let found = warnings("var a; println(a);");
+        assert!(matches!(found[..], [LintWarning::UninitializedRead(_)]), "{found:?}");
+    }
+
+    #[test]
+    fn reads_assigned_in_some_paths_are_reported() {
+        let found = warnings("var a; var b = true; if b { a = 1; } println(a);");
+        assert!(matches!(found[..], [LintWarning::MaybeUninitializedRead(_)]), "{found:?}");
+    }
+
+    #[test]
+    fn reads_assigned_in_every_path_are_fine() {
+        let found = warnings("var a; var b = true; if b { a = 1; } else { a = 2; } println(a);");
+        assert!(found.is_empty(), "{found:?}");
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+
+use rlox_ast::Ast;
+use rlox_ast::expr::{Expr, ExprKind};
+use rlox_ast::stmt::{Stmt, StmtId, StmtKind, StmtNode};
+use rlox_cf_graph::{BasicBlock, BasicBlockValue, CfgVec, ControlFlowGraph, Program, Terminator, reachability};
+use rlox_infra::StructVec;
+use rlox_interpreter::{Value, constant};
+use rlox_source::SourceMetadata;
+
+use crate::warning::{ConstantCondition, LintWarning, UnreachableCode};
+
+/// Conditions known before running the program, and the code they make unreachable. Each
+/// dead region is reported once. `while true` is how infinite loops are written, its
+/// condition is not reported, but the code after the loop is.
+pub fn check(program: &Program, ast: &Ast, warnings: &mut Vec<LintWarning>) {
+    let loops = while_stmts(ast);
+
+    for (_, cf_graph) in program.procedures.iter() {
+        let reachable = reachability::reachable(cf_graph, |node| constant_condition(node, ast));
+
+        for id in cf_graph.basic_block_ids().filter(|id| reachable[*id]) {
+            let basic_block: &BasicBlock = cf_graph.get(id);
+
+            let Terminator::Branch(node) = basic_block.terminator else {
+                continue;
+            };
+
+            let Some(value) = constant_condition(node, ast) else {
+                continue;
+            };
+
+            if value && loops.contains(&node.stmt_id) && matches!(node.inner.kind(), ExprKind::Boolean(true)) {
+                continue;
+            }
+
+            let metadata = *ast.get(node.inner.global_id());
+
+            warnings.push(
+                ConstantCondition {
+                    value,
+                    start: metadata.start,
+                    end: metadata.end,
+                    source: metadata.source,
+                }
+                .into(),
+            );
+        }
+
+        dead_regions(cf_graph, &reachable, ast, warnings);
+    }
+}
+
+fn constant_condition(node: StmtNode<Expr>, ast: &Ast) -> Option<bool> {
+    match constant::evaluate(node.inner, ast) {
+        Some(Value::Boolean(value)) => Some(value),
+        _ => None,
+    }
+}
+
+/// Unreachable blocks connected to each other form a region, blocks are created
+/// following the source code so the first block found starts its region.
+fn dead_regions(cf_graph: &ControlFlowGraph, reachable: &CfgVec<bool>, ast: &Ast, warnings: &mut Vec<LintWarning>) {
+    let mut visited = CfgVec::filled(false, cf_graph);
+
+    for start in cf_graph.basic_block_ids() {
+        if reachable[start] || visited[start] {
+            continue;
+        }
+
+        let mut span: Option<SourceMetadata> = None;
+        let mut stack = vec![start];
+        visited[start] = true;
+
+        while let Some(id) = stack.pop() {
+            for metadata in block_metadata(cf_graph.get(id), ast) {
+                span = Some(match span {
+                    None => metadata,
+                    Some(span) => SourceMetadata {
+                        start: span.start.min(metadata.start),
+                        end: span.end.max(metadata.end),
+                        source: span.source,
+                    },
+                });
+            }
+
+            for successor in cf_graph.successors(id).iter().copied() {
+                if !reachable[successor] && !visited[successor] {
+                    visited[successor] = true;
+                    stack.push(successor);
+                }
+            }
+        }
+
+        // Regions without statements, like the exit point after an infinite loop, are not code.
+        if let Some(span) = span {
+            warnings.push(
+                UnreachableCode {
+                    start: span.start,
+                    end: span.end,
+                    source: span.source,
+                }
+                .into(),
+            );
+        }
+    }
+}
+
+fn block_metadata(basic_block: &BasicBlock, ast: &Ast) -> Vec<SourceMetadata> {
+    let mut metadata: Vec<SourceMetadata> = basic_block
+        .stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            BasicBlockValue::Declaration(node) => Some(*ast.get(node.stmt_id)),
+            BasicBlockValue::StmtExpr(node) => Some(*ast.get(node.stmt_id)),
+            BasicBlockValue::EnterBlock(_) | BasicBlockValue::LeaveBlock(_) => None,
+        })
+        .collect();
+
+    if let Terminator::Branch(node) = basic_block.terminator {
+        metadata.push(*ast.get(node.stmt_id));
+    }
+
+    metadata
+}
+
+fn while_stmts(ast: &Ast) -> HashSet<StmtId> {
+    let mut loops = HashSet::new();
+
+    for stmt in ast.main().iter().copied() {
+        collect_while_stmts(stmt, ast, &mut loops);
+    }
+
+    for function in ast.functions() {
+        for stmt in ast[ast[function].body].iter().copied() {
+            collect_while_stmts(stmt, ast, &mut loops);
+        }
+    }
+
+    loops
+}
+
+fn collect_while_stmts(stmt: Stmt, ast: &Ast, loops: &mut HashSet<StmtId>) {
+    match stmt.kind() {
+        StmtKind::While(inner) => {
+            loops.insert(stmt.global_id());
+            collect_while_stmts(ast[inner].body, ast, loops);
+        }
+
+        StmtKind::IfElse(inner) => {
+            collect_while_stmts(ast[inner].if_branch, ast, loops);
+
+            if let Some(else_branch) = ast[inner].else_branch {
+                collect_while_stmts(else_branch, ast, loops);
+            }
+        }
+
+        StmtKind::Block(inner) => {
+            for stmt in ast[inner].iter().copied() {
+                collect_while_stmts(stmt, ast, loops);
+            }
+        }
+
+        StmtKind::Declaration(_) | StmtKind::Expr(_) => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use crate::lint;
+    use crate::warning::LintWarning;
+
+    fn warnings(code: &str) -> Vec<String> {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        lint(&ast)
+            .into_iter()
+            .filter(|warning| matches!(warning, LintWarning::UnreachableCode(_) | LintWarning::ConstantCondition(_)))
+            .map(|warning| {
+                let metadata = warning.source_metadata();
+                let span = code[metadata.start..metadata.end].trim();
+
+                format!("{}: {span}", warning.description())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn constant_branches_are_reported() {
+        let found = warnings("if 1 > 2 { println(1); println(2); } else { println(3); }");
+        assert_eq!(found, [
+            "This condition is always false: 1 > 2",
+            "This code is never executed: println(1); println(2);",
+        ]);
+    }
+
+    #[test]
+    fn code_after_infinite_loops_is_reported_once() {
+        let found = warnings("for(;;) { println(1); } println(2); if true or false { println(3); }");
+        assert_eq!(found, ["This code is never executed: println(2); if true or false { println(3); }"]);
+    }
+
+    #[test]
+    fn loops_with_constant_false_conditions_are_reported() {
+        let found = warnings("while false { println(1); }");
+        assert_eq!(found, ["This condition is always false: false", "This code is never executed: println(1);"]);
+    }
+}
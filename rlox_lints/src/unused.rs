@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+
+use rlox_ast::Ast;
+use rlox_cf_graph::Program;
+use rlox_cf_graph::liveness::Liveness;
+use rlox_cf_graph::variables::{self, Access, Declarator, Definition, VarId, Variables};
+use rlox_infra::StructVec;
+use rlox_source::SourceMetadata;
+
+use crate::warning::{DeadStore, LintWarning, UnusedVariable};
+
+/// Variables that are never read, and writes whose value is never read. The writes of
+/// a variable that is never read are all dead, only the declaration is reported for them.
+pub fn check(program: &Program, variables: &Variables, ast: &Ast, warnings: &mut Vec<LintWarning>) {
+    let mut read = HashSet::new();
+
+    for (_, cf_graph) in program.procedures.iter() {
+        for id in cf_graph.basic_block_ids() {
+            for access in variables::accesses(cf_graph.get(id), variables, ast) {
+                if let Access::Read(_, var) = access {
+                    read.insert(var);
+                }
+            }
+        }
+    }
+
+    for var in variables.ids().filter(|var| !read.contains(var)) {
+        let metadata = match variables.declarator(var) {
+            Declarator::Declaration(node) => *ast.get(node.stmt_id),
+            Declarator::Param(_, identifier) => *ast.get(identifier),
+        };
+
+        warnings.push(unused_variable(var, metadata, variables, ast));
+    }
+
+    for (_, cf_graph) in program.procedures.iter() {
+        let liveness = Liveness {
+            cf_graph,
+            variables,
+            ast,
+        };
+
+        for (definition, var) in liveness.dead_stores() {
+            if !read.contains(&var) {
+                continue;
+            }
+
+            let metadata = match definition {
+                Definition::Declaration(stmt) if has_value(var, variables, ast) => *ast.get(stmt),
+                // `var a;` stores nil implicitly, there is nothing to remove.
+                Definition::Declaration(_) => continue,
+                Definition::Assign(expr) => *ast.get(expr),
+                // Parameters are not written inside the body, they never appear as stores.
+                Definition::Param(..) => continue,
+            };
+
+            warnings.push(dead_store(var, metadata, variables, ast));
+        }
+    }
+}
+
+fn has_value(var: VarId, variables: &Variables, ast: &Ast) -> bool {
+    match variables.declarator(var) {
+        Declarator::Declaration(node) => ast[node.inner].value.is_some(),
+        Declarator::Param(..) => false,
+    }
+}
+
+fn unused_variable(var: VarId, metadata: SourceMetadata, variables: &Variables, ast: &Ast) -> LintWarning {
+    UnusedVariable {
+        name: variables.name(var, ast).to_string(),
+        start: metadata.start,
+        end: metadata.end,
+        source: metadata.source,
+    }
+    .into()
+}
+
+fn dead_store(var: VarId, metadata: SourceMetadata, variables: &Variables, ast: &Ast) -> LintWarning {
+    DeadStore {
+        name: variables.name(var, ast).to_string(),
+        start: metadata.start,
+        end: metadata.end,
+        source: metadata.source,
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use crate::lint;
+
+    fn warnings(code: &str) -> Vec<String> {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        lint(&ast)
+            .into_iter()
+            .map(|warning| {
+                let metadata = warning.source_metadata();
+                let span = code[metadata.start..metadata.end].trim();
+
+                format!("{}: {span}", warning.description())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unused_variables_are_reported_once() {
+        let found = warnings("var a = 1; a = 2; var b = 3; println(b);");
+        assert_eq!(found, ["Variable `a` is declared but never read: var a = 1;"]);
+    }
+
+    #[test]
+    fn declarations_without_value_are_not_dead_stores() {
+        let found = warnings("var a; a = 1; println(a);");
+        assert!(found.is_empty(), "{found:?}");
+    }
+
+    #[test]
+    fn overwritten_assignments_are_dead_stores() {
+        let found = warnings("var a = 1; a = 2; a = 3; println(a);");
+        assert_eq!(found, [
+            "The value stored in `a` is never read: var a = 1;",
+            "The value stored in `a` is never read: a = 2",
+        ]);
+    }
+}
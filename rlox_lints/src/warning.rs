@@ -0,0 +1,432 @@
+use rlox_errors::{Label, Message, Warning};
+use rlox_interpreter::value_system::Kind;
+use rlox_source::{Source, SourceMetadata};
+
+#[derive(Debug)]
+pub enum LintWarning {
+    UnusedVariable(UnusedVariable),
+    DeadStore(DeadStore),
+    UninitializedRead(UninitializedRead),
+    MaybeUninitializedRead(MaybeUninitializedRead),
+    UnreachableCode(UnreachableCode),
+    ConstantCondition(ConstantCondition),
+    UndefinedOperation(UndefinedOperation),
+    NonBooleanCondition(NonBooleanCondition),
+    NotCallable(NotCallable),
+}
+
+impl From<LintWarning> for Warning {
+    fn from(value: LintWarning) -> Self {
+        match value {
+            LintWarning::UnusedVariable(w) => w.into(),
+            LintWarning::DeadStore(w) => w.into(),
+            LintWarning::UninitializedRead(w) => w.into(),
+            LintWarning::MaybeUninitializedRead(w) => w.into(),
+            LintWarning::UnreachableCode(w) => w.into(),
+            LintWarning::ConstantCondition(w) => w.into(),
+            LintWarning::UndefinedOperation(w) => w.into(),
+            LintWarning::NonBooleanCondition(w) => w.into(),
+            LintWarning::NotCallable(w) => w.into(),
+        }
+    }
+}
+
+impl LintWarning {
+    pub fn code(&self) -> &'static str {
+        match self {
+            LintWarning::UnusedVariable(w) => w.code(),
+            LintWarning::DeadStore(w) => w.code(),
+            LintWarning::UninitializedRead(w) => w.code(),
+            LintWarning::MaybeUninitializedRead(w) => w.code(),
+            LintWarning::UnreachableCode(w) => w.code(),
+            LintWarning::ConstantCondition(w) => w.code(),
+            LintWarning::UndefinedOperation(w) => w.code(),
+            LintWarning::NonBooleanCondition(w) => w.code(),
+            LintWarning::NotCallable(w) => w.code(),
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            LintWarning::UnusedVariable(w) => w.description(),
+            LintWarning::DeadStore(w) => w.description(),
+            LintWarning::UninitializedRead(w) => w.description(),
+            LintWarning::MaybeUninitializedRead(w) => w.description(),
+            LintWarning::UnreachableCode(w) => w.description(),
+            LintWarning::ConstantCondition(w) => w.description(),
+            LintWarning::UndefinedOperation(w) => w.description(),
+            LintWarning::NonBooleanCondition(w) => w.description(),
+            LintWarning::NotCallable(w) => w.description(),
+        }
+    }
+
+    pub fn source_metadata(&self) -> SourceMetadata {
+        match self {
+            LintWarning::UnusedVariable(w) => w.source_metadata(),
+            LintWarning::DeadStore(w) => w.source_metadata(),
+            LintWarning::UninitializedRead(w) => w.source_metadata(),
+            LintWarning::MaybeUninitializedRead(w) => w.source_metadata(),
+            LintWarning::UnreachableCode(w) => w.source_metadata(),
+            LintWarning::ConstantCondition(w) => w.source_metadata(),
+            LintWarning::UndefinedOperation(w) => w.source_metadata(),
+            LintWarning::NonBooleanCondition(w) => w.source_metadata(),
+            LintWarning::NotCallable(w) => w.source_metadata(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnusedVariable {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+}
+
+impl From<UnusedVariable> for LintWarning {
+    fn from(value: UnusedVariable) -> Self {
+        LintWarning::UnusedVariable(value)
+    }
+}
+
+impl Message for UnusedVariable {
+    fn code(&self) -> &'static str {
+        "W0101"
+    }
+
+    fn description(&self) -> String {
+        format!("Variable `{}` is declared but never read", self.name)
+    }
+
+    fn help(&self) -> Option<String> {
+        Some(format!("Remove the declaration if `{}` is not needed", self.name))
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DeadStore {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+}
+
+impl From<DeadStore> for LintWarning {
+    fn from(value: DeadStore) -> Self {
+        LintWarning::DeadStore(value)
+    }
+}
+
+impl Message for DeadStore {
+    fn code(&self) -> &'static str {
+        "W0102"
+    }
+
+    fn description(&self) -> String {
+        format!("The value stored in `{}` is never read", self.name)
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UninitializedRead {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+    pub declaration: SourceMetadata,
+}
+
+impl From<UninitializedRead> for LintWarning {
+    fn from(value: UninitializedRead) -> Self {
+        LintWarning::UninitializedRead(value)
+    }
+}
+
+impl Message for UninitializedRead {
+    fn code(&self) -> &'static str {
+        "W0103"
+    }
+
+    fn description(&self) -> String {
+        format!("Variable `{}` is always nil here, no value is assigned before reading it", self.name)
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+
+    fn label(&self) -> Option<String> {
+        Some("read here".into())
+    }
+
+    fn labels(&self) -> Vec<Label> {
+        vec![Label {
+            message: "declared here without a value".into(),
+            metadata: self.declaration,
+        }]
+    }
+
+    fn help(&self) -> Option<String> {
+        Some(format!("Give `{}` a value when declaring it", self.name))
+    }
+}
+
+#[derive(Debug)]
+pub struct MaybeUninitializedRead {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+    pub declaration: SourceMetadata,
+}
+
+impl From<MaybeUninitializedRead> for LintWarning {
+    fn from(value: MaybeUninitializedRead) -> Self {
+        LintWarning::MaybeUninitializedRead(value)
+    }
+}
+
+impl Message for MaybeUninitializedRead {
+    fn code(&self) -> &'static str {
+        "W0104"
+    }
+
+    fn description(&self) -> String {
+        format!("Variable `{}` may be nil here, some paths do not assign a value before reading it", self.name)
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+
+    fn label(&self) -> Option<String> {
+        Some("read here".into())
+    }
+
+    fn labels(&self) -> Vec<Label> {
+        vec![Label {
+            message: "declared here without a value".into(),
+            metadata: self.declaration,
+        }]
+    }
+
+    fn help(&self) -> Option<String> {
+        Some(format!("Give `{}` a value when declaring it", self.name))
+    }
+}
+
+#[derive(Debug)]
+pub struct UnreachableCode {
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+}
+
+impl From<UnreachableCode> for LintWarning {
+    fn from(value: UnreachableCode) -> Self {
+        LintWarning::UnreachableCode(value)
+    }
+}
+
+impl Message for UnreachableCode {
+    fn code(&self) -> &'static str {
+        "W0105"
+    }
+
+    fn description(&self) -> String {
+        "This code is never executed".into()
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ConstantCondition {
+    pub value: bool,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+}
+
+impl From<ConstantCondition> for LintWarning {
+    fn from(value: ConstantCondition) -> Self {
+        LintWarning::ConstantCondition(value)
+    }
+}
+
+impl Message for ConstantCondition {
+    fn code(&self) -> &'static str {
+        "W0106"
+    }
+
+    fn description(&self) -> String {
+        format!("This condition is always {}", self.value)
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UndefinedOperation {
+    /// Whether every path fails, or only some of them.
+    pub certain: bool,
+    pub found: Vec<Kind>,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+}
+
+impl From<UndefinedOperation> for LintWarning {
+    fn from(value: UndefinedOperation) -> Self {
+        LintWarning::UndefinedOperation(value)
+    }
+}
+
+impl Message for UndefinedOperation {
+    fn code(&self) -> &'static str {
+        "W0107"
+    }
+
+    fn description(&self) -> String {
+        let operands = join_kinds(&self.found, " and ");
+
+        match self.certain {
+            true => format!("This operation always fails, it is not defined for {operands}"),
+            false => format!("This operation may fail, it is not defined for {operands}"),
+        }
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NonBooleanCondition {
+    /// Whether every path fails, or only some of them.
+    pub certain: bool,
+    pub found: Vec<Kind>,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+}
+
+impl From<NonBooleanCondition> for LintWarning {
+    fn from(value: NonBooleanCondition) -> Self {
+        LintWarning::NonBooleanCondition(value)
+    }
+}
+
+impl Message for NonBooleanCondition {
+    fn code(&self) -> &'static str {
+        "W0108"
+    }
+
+    fn description(&self) -> String {
+        let found = join_kinds(&self.found, " or ");
+
+        match self.certain {
+            true => format!("This condition is never a boolean, it can be {found}"),
+            false => format!("This condition may not be a boolean, it can be {found}"),
+        }
+    }
+
+    fn notes(&self) -> Vec<String> {
+        vec!["Conditions are not converted to booleans, only `true` and `false` are valid".into()]
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NotCallable {
+    /// Whether every path fails, or only some of them.
+    pub certain: bool,
+    pub found: Vec<Kind>,
+    pub start: usize,
+    pub end: usize,
+    pub source: Source,
+}
+
+impl From<NotCallable> for LintWarning {
+    fn from(value: NotCallable) -> Self {
+        LintWarning::NotCallable(value)
+    }
+}
+
+impl Message for NotCallable {
+    fn code(&self) -> &'static str {
+        "W0109"
+    }
+
+    fn description(&self) -> String {
+        let found = join_kinds(&self.found, " or ");
+
+        match self.certain {
+            true => format!("This is never a function, it can be {found}"),
+            false => format!("This may not be a function, it can be {found}"),
+        }
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+}
+
+fn join_kinds(kinds: &[Kind], separator: &str) -> String {
+    kinds
+        .iter()
+        .map(Kind::to_string)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
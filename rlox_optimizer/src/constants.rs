@@ -0,0 +1,399 @@
+//! Constant folding and propagation. Expressions whose value is known before
+//! running the program are replaced by a literal with the same value. A read
+//! of a variable is known when the only definition reaching it stores a known
+//! value, so `var x = 10; println(x * 2);` becomes `var x = 10; println(20);`.
+
+use std::collections::HashMap;
+
+use rlox_ast::expr::{Expr, ExprId, ExprKind, Nil, Unary, UnaryOperator};
+use rlox_ast::stmt::{Stmt, StmtId, StmtKind};
+use rlox_ast::{Ast, AstElem, StrId};
+use rlox_cf_graph::build_cfg;
+use rlox_cf_graph::reaching_definitions::ReachingDefinitions;
+use rlox_cf_graph::variables::{self, Definition};
+use rlox_infra::StructVec;
+use rlox_interpreter::{Value, constant};
+use rlox_source::SourceMetadata;
+
+pub fn fold(ast: &mut Ast) {
+    let mut propagation = Propagation::new(ast);
+
+    let main_len = ast.main().len();
+    for index in 0..main_len {
+        let stmt = ast.main()[index];
+        let folded = fold_stmt(stmt, &mut propagation, ast);
+        ast.main_mut()[index] = folded;
+    }
+
+    for function in ast.functions().collect::<Vec<_>>() {
+        let body = ast[function].body;
+
+        for index in 0..ast[body].len() {
+            let stmt = ast[body][index];
+            let folded = fold_stmt(stmt, &mut propagation, ast);
+            ast[body][index] = folded;
+        }
+    }
+}
+
+/// Values of the definitions of the program, computed the first time they are needed.
+struct Propagation {
+    reaching: HashMap<ExprId, Vec<Definition>>,
+    declarations: HashMap<StmtId, Option<Expr>>,
+    assigns: HashMap<ExprId, Expr>,
+    values: HashMap<Definition, Option<Value>>,
+}
+
+impl Propagation {
+    fn new(ast: &Ast) -> Propagation {
+        let program = build_cfg::from_program(ast);
+        let variables = variables::resolve(ast);
+
+        let mut propagation = Propagation {
+            reaching: HashMap::new(),
+            declarations: HashMap::new(),
+            assigns: HashMap::new(),
+            values: HashMap::new(),
+        };
+
+        for (procedure, cf_graph) in program.procedures.iter() {
+            let reaching_definitions = ReachingDefinitions {
+                procedure: *procedure,
+                cf_graph,
+                variables: &variables,
+                ast,
+            };
+
+            for read in reaching_definitions.uses() {
+                propagation.reaching.insert(read.expr, read.definitions);
+            }
+        }
+
+        for stmt in ast.main().iter().copied() {
+            propagation.collect_stmt(stmt, ast);
+        }
+
+        for function in ast.functions() {
+            for stmt in ast[ast[function].body].iter().copied() {
+                propagation.collect_stmt(stmt, ast);
+            }
+        }
+
+        propagation
+    }
+
+    fn collect_stmt(&mut self, stmt: Stmt, ast: &Ast) {
+        match stmt.kind() {
+            StmtKind::Expr(expr) => self.collect_expr(expr, ast),
+
+            StmtKind::Declaration(inner) => {
+                let value = ast[inner].value;
+                self.declarations.insert(stmt.global_id(), value);
+
+                if let Some(value) = value {
+                    self.collect_expr(value, ast);
+                }
+            }
+
+            StmtKind::Block(inner) => {
+                for stmt in ast[inner].iter().copied() {
+                    self.collect_stmt(stmt, ast);
+                }
+            }
+
+            StmtKind::IfElse(inner) => {
+                let if_else = &ast[inner];
+
+                self.collect_expr(if_else.condition, ast);
+                self.collect_stmt(if_else.if_branch, ast);
+
+                if let Some(else_branch) = if_else.else_branch {
+                    self.collect_stmt(else_branch, ast);
+                }
+            }
+
+            StmtKind::While(inner) => {
+                self.collect_expr(ast[inner].condition, ast);
+                self.collect_stmt(ast[inner].body, ast);
+            }
+        }
+    }
+
+    fn collect_expr(&mut self, expr: Expr, ast: &Ast) {
+        match expr.kind() {
+            ExprKind::Assign(inner) => {
+                self.assigns.insert(expr.global_id(), ast[inner].rhs);
+                self.collect_expr(ast[inner].rhs, ast);
+            }
+
+            ExprKind::Binary(inner) => {
+                self.collect_expr(ast[inner].lhs, ast);
+                self.collect_expr(ast[inner].rhs, ast);
+            }
+
+            ExprKind::Unary(inner) => self.collect_expr(ast[inner].operand, ast),
+
+            ExprKind::Call(inner) => {
+                for argument in ast[inner].arguments.iter().copied() {
+                    self.collect_expr(argument, ast);
+                }
+            }
+
+            ExprKind::Identifier(_)
+            | ExprKind::String(_)
+            | ExprKind::Natural(_)
+            | ExprKind::Decimal(_)
+            | ExprKind::Boolean(_)
+            | ExprKind::Nil => (),
+        }
+    }
+
+    fn evaluate(&mut self, expr: Expr, ast: &Ast) -> Option<Value> {
+        constant::evaluate_with(expr, ast, &mut |read| self.read(read, ast))
+    }
+
+    fn read(&mut self, read: Expr, ast: &Ast) -> Option<Value> {
+        let &[definition] = &self.reaching.get(&read.global_id())?[..] else {
+            return None;
+        };
+
+        self.definition(definition, ast)
+    }
+
+    fn definition(&mut self, definition: Definition, ast: &Ast) -> Option<Value> {
+        if let Some(value) = self.values.get(&definition) {
+            return value.clone();
+        }
+
+        // A definition depending on itself is never constant.
+        self.values.insert(definition, None);
+
+        let value = match definition {
+            Definition::Param(..) => None,
+            Definition::Declaration(stmt) => match *self.declarations.get(&stmt)? {
+                Some(value) => self.evaluate(value, ast),
+                None => Some(Value::Nil),
+            },
+            Definition::Assign(expr) => {
+                let rhs = *self.assigns.get(&expr)?;
+                self.evaluate(rhs, ast)
+            }
+        };
+
+        self.values.insert(definition, value.clone());
+        value
+    }
+}
+
+fn fold_stmt(stmt: Stmt, propagation: &mut Propagation, ast: &mut Ast) -> Stmt {
+    match stmt.kind() {
+        StmtKind::Expr(expr) => {
+            let folded = fold_expr(expr, propagation, ast);
+
+            if folded.global_id() == expr.global_id() {
+                return stmt;
+            }
+
+            let folded_stmt: Stmt = ast.add(folded);
+            let metadata = *ast.get(stmt.global_id());
+            ast.assign(folded_stmt.global_id(), metadata);
+
+            folded_stmt
+        }
+
+        StmtKind::Declaration(inner) => {
+            if let Some(value) = ast[inner].value {
+                ast[inner].value = Some(fold_expr(value, propagation, ast));
+            }
+
+            stmt
+        }
+
+        StmtKind::Block(inner) => {
+            for index in 0..ast[inner].len() {
+                let nested = ast[inner][index];
+                ast[inner][index] = fold_stmt(nested, propagation, ast);
+            }
+
+            stmt
+        }
+
+        StmtKind::IfElse(inner) => {
+            let if_else = ast[inner].clone();
+
+            ast[inner].condition = fold_expr(if_else.condition, propagation, ast);
+            ast[inner].if_branch = fold_stmt(if_else.if_branch, propagation, ast);
+
+            if let Some(else_branch) = if_else.else_branch {
+                ast[inner].else_branch = Some(fold_stmt(else_branch, propagation, ast));
+            }
+
+            stmt
+        }
+
+        StmtKind::While(inner) => {
+            let while_stmt = ast[inner].clone();
+
+            ast[inner].condition = fold_expr(while_stmt.condition, propagation, ast);
+            ast[inner].body = fold_stmt(while_stmt.body, propagation, ast);
+
+            stmt
+        }
+    }
+}
+
+/// Returns the expression itself when it is not replaced, nested expressions may still be.
+fn fold_expr(expr: Expr, propagation: &mut Propagation, ast: &mut Ast) -> Expr {
+    if !is_literal(expr, ast) {
+        let value = propagation.evaluate(expr, ast);
+        let metadata = *ast.get(expr.global_id());
+
+        if let Some(literal) = value.and_then(|value| literal(value, metadata, ast)) {
+            return literal;
+        }
+    }
+
+    match expr.kind() {
+        ExprKind::Assign(inner) => {
+            let rhs = ast[inner].rhs;
+            ast[inner].rhs = fold_expr(rhs, propagation, ast);
+        }
+
+        ExprKind::Binary(inner) => {
+            let binary = ast[inner];
+            ast[inner].lhs = fold_expr(binary.lhs, propagation, ast);
+            ast[inner].rhs = fold_expr(binary.rhs, propagation, ast);
+        }
+
+        ExprKind::Unary(inner) => {
+            let operand = ast[inner].operand;
+            ast[inner].operand = fold_expr(operand, propagation, ast);
+        }
+
+        ExprKind::Call(inner) => {
+            for index in 0..ast[inner].arguments.len() {
+                let argument = ast[inner].arguments[index];
+                ast[inner].arguments[index] = fold_expr(argument, propagation, ast);
+            }
+        }
+
+        ExprKind::Identifier(_)
+        | ExprKind::String(_)
+        | ExprKind::Natural(_)
+        | ExprKind::Decimal(_)
+        | ExprKind::Boolean(_)
+        | ExprKind::Nil => (),
+    }
+
+    expr
+}
+
+/// Literals, and negative numbers which are written as negated literals.
+fn is_literal(expr: Expr, ast: &Ast) -> bool {
+    match expr.kind() {
+        ExprKind::String(_) | ExprKind::Natural(_) | ExprKind::Decimal(_) | ExprKind::Boolean(_) | ExprKind::Nil => {
+            true
+        }
+
+        ExprKind::Unary(inner) => {
+            matches!(ast[inner].operator, UnaryOperator::Minus) && is_literal(ast[inner].operand, ast)
+        }
+
+        ExprKind::Identifier(_) | ExprKind::Assign(_) | ExprKind::Binary(_) | ExprKind::Call(_) => false,
+    }
+}
+
+/// Expression evaluating to `value`, every node gets the metadata of the folded expression.
+fn literal(value: Value, metadata: SourceMetadata, ast: &mut Ast) -> Option<Expr> {
+    let expr: Expr = match value {
+        Value::Nil => ast.add(Nil),
+        Value::Boolean(inner) => ast.add(inner),
+        Value::Decimal(inner) => ast.add(inner),
+        Value::Natural(inner) => ast.add(inner),
+        Value::String(inner) => {
+            let string: StrId = ast.add(inner.as_bytes());
+            ast.add(string)
+        }
+
+        // There are no signed literals, `-n` evaluates to a negative signed value and
+        // `--n` to a positive one. The minimum value can not be negated.
+        Value::Signed(i64::MIN) => return None,
+        Value::Signed(inner) => {
+            let natural = literal(Value::Natural(inner.unsigned_abs()), metadata, ast)?;
+            let negative = negate(natural, metadata, ast);
+
+            match inner > 0 {
+                true => negate(negative, metadata, ast),
+                false => negative,
+            }
+        }
+
+        Value::Addr(_) | Value::Fn(_) => return None,
+    };
+
+    ast.assign(expr.global_id(), metadata);
+    Some(expr)
+}
+
+fn negate(operand: Expr, metadata: SourceMetadata, ast: &mut Ast) -> Expr {
+    let expr: Expr = ast.add(Unary {
+        operator: UnaryOperator::Minus,
+        operand,
+    });
+
+    ast.assign(expr.global_id(), metadata);
+    expr
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_ast::debug_utils::fmt_stmt;
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use super::*;
+
+    fn folded(code: &str) -> Vec<String> {
+        let Ok(mut ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        fold(&mut ast);
+
+        ast.main()
+            .iter()
+            .map(|stmt| fmt_stmt(*stmt, &ast))
+            .collect()
+    }
+
+    #[test]
+    fn literal_arithmetic_is_folded() {
+        assert_eq!(folded("println(3 * 4 + 1);"), ["Call(println, [\"Natural(13)\"])"]);
+        assert_eq!(folded("println(-1 - 2);"), ["Call(println, [\"Minus(Natural(3))\"])"]);
+        assert_eq!(folded("println(-1 * -3);"), ["Call(println, [\"Minus(Minus(Natural(3)))\"])"]);
+        assert_eq!(folded("println(1 < 2 and !false);"), ["Call(println, [\"Boolean(true)\"])"]);
+    }
+
+    #[test]
+    fn constants_are_propagated() {
+        assert_eq!(folded("var x = 10; var y = x * 2; println(y);"), [
+            "Declaration(x, Natural(10))",
+            "Declaration(y, Natural(20))",
+            "Call(println, [\"Natural(20)\"])",
+        ]);
+    }
+
+    #[test]
+    fn variables_with_several_definitions_are_kept() {
+        assert_eq!(folded("var x = 1; while x < 10 { x = x + 1; }"), [
+            "Declaration(x, Natural(1))",
+            "While(Less(x, Natural(10)),Block([\"Assign(x, Plus(x, Natural(1)))\"]))",
+        ]);
+    }
+
+    #[test]
+    fn failing_operations_are_kept() {
+        assert_eq!(folded("println(1 / 0);"), ["Call(println, [\"Division(Natural(1), Natural(0))\"])"]);
+        assert_eq!(folded("println(1 + true);"), ["Call(println, [\"Plus(Natural(1), Boolean(true))\"])"]);
+    }
+}
@@ -0,0 +1,225 @@
+//! Dead code elimination. Branches that can not be taken are pruned, the
+//! statements after an infinite loop are removed and so are the expression
+//! statements without effects. Works best after folding the constants, which
+//! turns most known conditions into literals.
+//!
+//! The pass walks the AST instead of using `rlox_cf_graph::reachability`, since it
+//! removes statements from the tree. Without `break` or `return`, code can only be
+//! dead after a constant condition or an infinite loop, and both are found here with
+//! the same `constant::evaluate` the unreachable code lint gives to the CFG analysis.
+
+use rlox_ast::expr::{Expr, ExprKind};
+use rlox_ast::stmt::{Stmt, StmtKind};
+use rlox_ast::{Ast, AstElem};
+use rlox_infra::StructVec;
+use rlox_interpreter::{Value, constant};
+
+pub fn eliminate(ast: &mut Ast) {
+    let main = ast.main().to_vec();
+    *ast.main_mut() = eliminate_in_sequence(&main, ast);
+
+    for function in ast.functions().collect::<Vec<_>>() {
+        let body = ast[function].body;
+        let stmts = ast[body].to_vec();
+        let pruned = eliminate_in_sequence(&stmts, ast);
+
+        // Function bodies are blocks of a fixed size, removed statements become empty blocks.
+        for (index, slot) in stmts.iter().enumerate() {
+            ast[body][index] = match pruned.get(index) {
+                Some(stmt) => *stmt,
+                None => empty_block(*slot, ast),
+            };
+        }
+    }
+}
+
+/// What is left of a statement once its dead code is removed.
+struct Pruned {
+    stmt: Option<Stmt>,
+    // The statements after it never run.
+    diverges: bool,
+}
+
+fn eliminate_in_sequence(stmts: &[Stmt], ast: &mut Ast) -> Vec<Stmt> {
+    let mut live = Vec::with_capacity(stmts.len());
+
+    for stmt in stmts.iter().copied() {
+        let pruned = eliminate_in_stmt(stmt, ast);
+        live.extend(pruned.stmt);
+
+        if pruned.diverges {
+            break;
+        }
+    }
+
+    live
+}
+
+fn eliminate_in_stmt(stmt: Stmt, ast: &mut Ast) -> Pruned {
+    match stmt.kind() {
+        StmtKind::Expr(expr) => Pruned {
+            stmt: (!is_pure(expr, ast)).then_some(stmt),
+            diverges: false,
+        },
+
+        StmtKind::Declaration(_) => Pruned {
+            stmt: Some(stmt),
+            diverges: false,
+        },
+
+        StmtKind::Block(inner) => {
+            let stmts = ast[inner].to_vec();
+            let live = eliminate_in_sequence(&stmts, ast);
+            let diverges = live.last().is_some_and(|last| diverges(*last, ast));
+
+            let stmt = match live.len() == stmts.len() {
+                true => stmt,
+                false => {
+                    let block: Stmt = ast.add(live.as_slice());
+                    let metadata = *ast.get(stmt.global_id());
+                    ast.assign(block.global_id(), metadata);
+                    block
+                }
+            };
+
+            Pruned {
+                stmt: Some(stmt),
+                diverges,
+            }
+        }
+
+        StmtKind::IfElse(inner) => {
+            let if_else = ast[inner].clone();
+
+            match condition(if_else.condition, ast) {
+                Some(true) => eliminate_in_stmt(if_else.if_branch, ast),
+
+                Some(false) => match if_else.else_branch {
+                    Some(else_branch) => eliminate_in_stmt(else_branch, ast),
+                    None => Pruned {
+                        stmt: None,
+                        diverges: false,
+                    },
+                },
+
+                None => {
+                    ast[inner].if_branch = eliminate_in_stmt(if_else.if_branch, ast)
+                        .stmt
+                        .unwrap_or_else(|| empty_block(if_else.if_branch, ast));
+
+                    if let Some(else_branch) = if_else.else_branch {
+                        ast[inner].else_branch = eliminate_in_stmt(else_branch, ast).stmt;
+                    }
+
+                    Pruned {
+                        stmt: Some(stmt),
+                        diverges: false,
+                    }
+                }
+            }
+        }
+
+        StmtKind::While(inner) => {
+            let while_stmt = ast[inner].clone();
+
+            match condition(while_stmt.condition, ast) {
+                Some(false) => Pruned {
+                    stmt: None,
+                    diverges: false,
+                },
+
+                known => {
+                    ast[inner].body = eliminate_in_stmt(while_stmt.body, ast)
+                        .stmt
+                        .unwrap_or_else(|| empty_block(while_stmt.body, ast));
+
+                    // There is no way of leaving a loop other than its condition.
+                    Pruned {
+                        stmt: Some(stmt),
+                        diverges: known == Some(true),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A block ending in an infinite loop never lets the statements after it run.
+fn diverges(stmt: Stmt, ast: &Ast) -> bool {
+    match stmt.kind() {
+        StmtKind::While(inner) => condition(ast[inner].condition, ast) == Some(true),
+        StmtKind::Block(inner) => ast[inner].last().is_some_and(|last| diverges(*last, ast)),
+        StmtKind::Expr(_) | StmtKind::Declaration(_) | StmtKind::IfElse(_) => false,
+    }
+}
+
+fn condition(condition: Expr, ast: &Ast) -> Option<bool> {
+    match constant::evaluate(condition, ast) {
+        Some(Value::Boolean(value)) => Some(value),
+        _ => None,
+    }
+}
+
+/// Expressions with a known value can not fail nor change anything when evaluated.
+fn is_pure(expr: Expr, ast: &Ast) -> bool {
+    match expr.kind() {
+        ExprKind::Assign(_) | ExprKind::Call(_) | ExprKind::Identifier(_) => false,
+        _ => constant::evaluate(expr, ast).is_some(),
+    }
+}
+
+/// Statements that must be kept, like the body of a loop, become an empty block.
+fn empty_block(replaced: Stmt, ast: &mut Ast) -> Stmt {
+    let block: Stmt = ast.add([].as_slice());
+    let metadata = *ast.get(replaced.global_id());
+    ast.assign(block.global_id(), metadata);
+
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_ast::debug_utils::fmt_stmt;
+    use rlox_errors::Diagnostics;
+    use rlox_source::Source;
+
+    use super::*;
+
+    fn eliminated(code: &str) -> Vec<String> {
+        let Ok(mut ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        eliminate(&mut ast);
+
+        ast.main()
+            .iter()
+            .map(|stmt| fmt_stmt(*stmt, &ast))
+            .collect()
+    }
+
+    #[test]
+    fn pure_expression_statements_are_removed() {
+        assert_eq!(eliminated("1 + 1; println(1); 1 + true;"), [
+            "Call(println, [\"Natural(1)\"])",
+            "Plus(Natural(1), Boolean(true))",
+        ]);
+    }
+
+    #[test]
+    fn constant_branches_are_pruned() {
+        assert_eq!(eliminated("if 1 > 2 { println(1); } else { println(2); } while false { println(3); }"), [
+            "Block([\"Call(println, [\\\"Natural(2)\\\"])\"])",
+        ]);
+    }
+
+    #[test]
+    fn code_after_infinite_loops_is_removed() {
+        assert_eq!(eliminated("{ println(1); while true { 2; } println(3); } println(4);"), [
+            "Block([\"Call(println, [\\\"Natural(1)\\\"])\", \"While(Boolean(true),Block([]))\"])",
+        ]);
+        assert_eq!(eliminated("{ println(1); while true { } } println(4);"), [
+            "Block([\"Call(println, [\\\"Natural(1)\\\"])\", \"While(Boolean(true),Block([]))\"])",
+        ]);
+    }
+}
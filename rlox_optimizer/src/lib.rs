@@ -0,0 +1,12 @@
+//! Optimizations over the AST. Every pass rewrites the program in place, and the
+//! result must behave exactly like the original one with any backend.
+
+pub mod constants;
+pub mod dead_code;
+
+use rlox_ast::Ast;
+
+pub fn optimize(ast: &mut Ast) {
+    constants::fold(ast);
+    dead_code::eliminate(ast);
+}
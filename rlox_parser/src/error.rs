@@ -1,6 +1,7 @@
-use rlox_errors::{Error, Message};
+use rlox_errors::{Edit, Error, Label, Message};
 use rlox_source::{Source, SourceMetadata};
 
+use crate::statement;
 use crate::token_stream::TokenKind;
 
 #[derive(Debug)]
@@ -8,6 +9,7 @@ pub enum ParserError {
     UnknownToken(UnknownToken),
     UnexpectedToken(UnexpectedToken),
     TypeCouldNotBeParsed(TypeCouldNotBeParsed),
+    TooManyErrors(TooManyErrors),
 }
 
 impl From<ParserError> for Error {
@@ -16,6 +18,7 @@ impl From<ParserError> for Error {
             ParserError::UnknownToken(e) => e.into(),
             ParserError::TypeCouldNotBeParsed(e) => e.into(),
             ParserError::UnexpectedToken(e) => e.into(),
+            ParserError::TooManyErrors(e) => e.into(),
         }
     }
 }
@@ -34,6 +37,10 @@ impl From<UnknownToken> for ParserError {
 }
 
 impl Message for UnknownToken {
+    fn code(&self) -> &'static str {
+        "E0101"
+    }
+
     fn description(&self) -> String {
         "Unknown token found".into()
     }
@@ -61,6 +68,10 @@ impl From<TypeCouldNotBeParsed> for ParserError {
 }
 
 impl Message for TypeCouldNotBeParsed {
+    fn code(&self) -> &'static str {
+        "E0103"
+    }
+
     fn description(&self) -> String {
         "Natural value could not be parsed".into()
     }
@@ -80,6 +91,25 @@ pub struct UnexpectedToken {
     pub(crate) end: usize,
     pub(crate) source: Source,
     pub(crate) expected: Vec<TokenKind>,
+    pub(crate) found: TokenKind,
+    pub(crate) misspelling: Option<Misspelling>,
+    /// Offset where the missing token is inserted, see `Context::missing`.
+    pub(crate) insertion: Option<(usize, &'static str)>,
+}
+
+/// An identifier written where a statement starts, close to one of its keywords.
+#[derive(Debug)]
+pub struct Misspelling {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) keyword: &'static str,
+}
+
+impl Misspelling {
+    /// Whether the parser knows the statement of the keyword, it is reserved otherwise.
+    pub(crate) fn is_supported(&self) -> bool {
+        statement::STMT_KEYWORDS.contains(&self.keyword)
+    }
 }
 
 impl From<UnexpectedToken> for ParserError {
@@ -89,8 +119,95 @@ impl From<UnexpectedToken> for ParserError {
 }
 
 impl Message for UnexpectedToken {
+    fn code(&self) -> &'static str {
+        "E0102"
+    }
+
+    fn description(&self) -> String {
+        format!("Expected one of the following tokens: {:?}, found {:?}", self.expected, self.found)
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+
+    fn labels(&self) -> Vec<Label> {
+        self.misspelling
+            .iter()
+            .map(|misspelling| Label {
+                message: format!("similar to the keyword `{}`", misspelling.keyword),
+                metadata: SourceMetadata {
+                    start: misspelling.start,
+                    end: misspelling.end,
+                    source: self.source,
+                },
+            })
+            .collect()
+    }
+
+    fn help(&self) -> Option<String> {
+        match (&self.misspelling, &self.insertion) {
+            (Some(misspelling), _) => match misspelling.is_supported() {
+                true => Some(format!("Maybe you meant `{}`", misspelling.keyword)),
+                false => Some(format!("Maybe you meant `{}`, which is not supported yet", misspelling.keyword)),
+            },
+            (None, Some((_, token))) => Some(format!("Add `{token}` after the previous token")),
+            (None, None) => None,
+        }
+    }
+
+    /// A misspelled keyword is replaced, the missing token is not inserted after it.
+    /// Keywords the parser does not know are not written, the code would still fail.
+    fn edits(&self) -> Vec<Edit> {
+        match (&self.misspelling, &self.insertion) {
+            (Some(misspelling), _) if !misspelling.is_supported() => Vec::new(),
+            (Some(misspelling), _) => vec![Edit {
+                metadata: SourceMetadata {
+                    start: misspelling.start,
+                    end: misspelling.end,
+                    source: self.source,
+                },
+                replacement: misspelling.keyword.into(),
+            }],
+            (None, insertion) => insertion
+                .iter()
+                .map(|(offset, token)| Edit {
+                    metadata: SourceMetadata {
+                        start: *offset,
+                        end: *offset,
+                        source: self.source,
+                    },
+                    replacement: (*token).into(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TooManyErrors {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) source: Source,
+}
+
+impl From<TooManyErrors> for ParserError {
+    fn from(value: TooManyErrors) -> Self {
+        ParserError::TooManyErrors(value)
+    }
+}
+
+impl Message for TooManyErrors {
+    fn code(&self) -> &'static str {
+        "E0104"
+    }
+
     fn description(&self) -> String {
-        format!("Expected one of the following tokens: {:?}", self.expected)
+        "Too many syntax errors, the code after this point was not parsed".into()
     }
 
     fn source_metadata(&self) -> SourceMetadata {
@@ -276,6 +276,10 @@ fn call_arguments(ctxt: &mut Context, ast: &mut Ast) -> ParserResult<Vec<Expr>>
         args.push(expression(ctxt, ast)?);
     }
 
+    if !matches!(ctxt.peek().kind, TokenKind::RightParen) {
+        return Err(ctxt.missing(vec![TokenKind::Comma, TokenKind::RightParen], ")"));
+    }
+
     Ok(args)
 }
 
@@ -325,19 +329,19 @@ fn primary(ctxt: &mut Context, ast: &mut Ast) -> ParserResult<Expr> {
             Ok(ast.add(identifier))
         }
 
-        _ => Err(Into::into(error::UnexpectedToken {
-            start: token.start,
-            end: token.end,
-            source: ctxt.src_id,
-            expected: vec![
-                TokenKind::False,
-                TokenKind::True,
-                TokenKind::Nil,
-                TokenKind::Integer,
-                TokenKind::Decimal,
-                TokenKind::LeftParen,
-            ],
-        })),
+        // Unary operators are also valid at the start of an operand.
+        _ => Err(ctxt.unexpected(vec![
+            TokenKind::False,
+            TokenKind::True,
+            TokenKind::Nil,
+            TokenKind::Integer,
+            TokenKind::Decimal,
+            TokenKind::String,
+            TokenKind::Identifier,
+            TokenKind::LeftParen,
+            TokenKind::Bang,
+            TokenKind::Minus,
+        ])),
     }?;
 
     ctxt.consume();
@@ -368,12 +372,7 @@ fn nested_expression(ctxt: &mut Context, ast: &mut Ast) -> ParserResult<Expr> {
     let inner = expression(ctxt, ast)?;
 
     if !matches!(ctxt.peek().kind, TokenKind::RightParen) {
-        return Err(Into::into(error::UnexpectedToken {
-            start: ctxt.peek().start,
-            end: ctxt.peek().end,
-            source: ctxt.src_id,
-            expected: vec![TokenKind::RightParen],
-        }));
+        return Err(ctxt.missing(vec![TokenKind::RightParen], ")"));
     };
 
     Ok(inner)
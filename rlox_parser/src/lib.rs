@@ -5,20 +5,43 @@ mod token_stream;
 
 use error::ParserError;
 use rlox_ast::Ast;
-use rlox_source::Source;
+use rlox_errors::Diagnostics;
+use rlox_source::{Source, SourceMetadata};
 use token_stream::{Token, TokenKind, TokenStream};
 
 type ParserResult<T> = Result<T, ParserError>;
 
+/// Errors reported before parsing stops, more of them are usually caused by the first.
+const MAX_ERRORS: usize = 20;
+
+/// Keywords that start a statement, parsing goes on from them after an error.
+const RECOVERY_KEYWORDS: &[TokenKind] = &[
+    TokenKind::Var,
+    TokenKind::If,
+    TokenKind::While,
+    TokenKind::For,
+    TokenKind::Fun,
+    TokenKind::Class,
+    TokenKind::Return,
+];
+
 struct Context<'a> {
     src: &'a [u8],
     src_id: Source,
     current: Token,
+    /// End of the last token consumed.
+    previous_end: usize,
     stream: TokenStream<'a>,
+    errors: Vec<ParserError>,
+    /// Warnings allowed by `lox-allow` comments, waiting for the next statement
+    /// of the same block. Dropped when the block or the file ends first.
+    pending_allows: Vec<&'static str>,
+    /// Warnings allowed in parts of the code, by `lox-allow` and `lox-allow-file` comments.
+    allowed: Vec<(&'static str, SourceMetadata)>,
 }
 
 impl Context<'_> {
-    fn new(src_id: Source, src: &[u8]) -> Context {
+    fn new(src_id: Source, src: &[u8]) -> Context<'_> {
         let mut stream = TokenStream::new(src);
         let start = stream.next_token();
 
@@ -27,6 +50,10 @@ impl Context<'_> {
             src_id,
             stream,
             current: start,
+            previous_end: 0,
+            errors: Vec::new(),
+            pending_allows: Vec::new(),
+            allowed: Vec::new(),
         }
     }
 
@@ -44,20 +71,70 @@ impl Context<'_> {
     }
 
     fn try_consume(&mut self, expected: TokenKind) -> ParserResult<Token> {
-        if self.peek().kind == expected {
-            Ok(self.consume())
-        } else {
-            Err(Into::into(error::UnexpectedToken {
+        match (self.peek().kind, expected) {
+            (found, expected) if found == expected => Ok(self.consume()),
+            (_, TokenKind::Semicolon) => Err(self.missing(vec![expected], ";")),
+            _ => Err(self.unexpected(vec![expected])),
+        }
+    }
+
+    /// Error for the current token, when one of `expected` should be there.
+    fn unexpected(&self, expected: Vec<TokenKind>) -> ParserError {
+        Into::into(self.unexpected_token(expected))
+    }
+
+    /// Same as [`Context::unexpected`], for a token that closes the code before it.
+    /// The error inserts `closing` after that code when the current token can not
+    /// continue it: it is in another line or it ends a statement or a block.
+    fn missing(&self, expected: Vec<TokenKind>, closing: &'static str) -> ParserError {
+        let found = self.peek();
+        let mut error = self.unexpected_token(expected);
+
+        let other_line = self.src[self.previous_end..found.start].contains(&b'\n');
+        let ends_code = matches!(found.kind, TokenKind::Semicolon | TokenKind::RightBrace | TokenKind::Eof);
+
+        if other_line || ends_code {
+            error.insertion = Some((self.previous_end, closing));
+        }
+
+        Into::into(error)
+    }
+
+    fn unexpected_token(&self, expected: Vec<TokenKind>) -> error::UnexpectedToken {
+        error::UnexpectedToken {
+            start: self.peek().start,
+            end: self.peek().end,
+            source: self.src_id,
+            expected,
+            found: self.peek().kind,
+            misspelling: None,
+            insertion: None,
+        }
+    }
+
+    /// Keeps the error. Once there are too many, the rest of the code is skipped.
+    fn error(&mut self, error: ParserError) {
+        if self.errors.len() > MAX_ERRORS {
+            return;
+        }
+
+        self.errors.push(error);
+
+        if self.errors.len() == MAX_ERRORS {
+            self.errors.push(Into::into(error::TooManyErrors {
                 start: self.peek().start,
                 end: self.peek().end,
                 source: self.src_id,
-                expected: vec![TokenKind::Semicolon],
-            }))
+            }));
+
+            self.stream.skip_to_end();
+            self.current = self.stream.next_token();
         }
     }
 
     fn consume(&mut self) -> Token {
         let current = self.current;
+        self.previous_end = current.end;
         self.current = self.stream.next_token();
 
         self.skip_comments();
@@ -67,27 +144,76 @@ impl Context<'_> {
 
     fn skip_comments(&mut self) {
         while matches!(self.current.kind, TokenKind::Comment) {
+            self.comment(self.current);
             self.current = self.stream.next_token();
         }
     }
 
+    /// Comments like `// lox-allow: unused-variable, W0102` allow warnings in the next
+    /// statement, and `// lox-allow-file: ...` in the whole file. Unknown names are ignored.
+    fn comment(&mut self, token: Token) {
+        let text = String::from_utf8_lossy(&self.src[token.start..token.end]);
+        let text = text.trim_start_matches('/').trim();
+
+        let (codes, whole_file) = match (text.strip_prefix("lox-allow:"), text.strip_prefix("lox-allow-file:")) {
+            (Some(codes), _) => (codes, false),
+            (_, Some(codes)) => (codes, true),
+            (None, None) => return,
+        };
+
+        let codes = codes
+            .split(',')
+            .filter_map(|name| rlox_errors::codes::warning(name.trim()));
+
+        match whole_file {
+            true => {
+                let file = SourceMetadata {
+                    start: 0,
+                    end: usize::MAX,
+                    source: self.src_id,
+                };
+
+                self.allowed.extend(codes.map(|code| (code, file)));
+            }
+            false => self.pending_allows.extend(codes),
+        }
+    }
+
     fn is_at_end(&self) -> bool {
         matches!(self.current.kind, TokenKind::Eof)
     }
 }
 
-fn panic_mode(ctxt: &mut Context) {
+/// Skips the rest of a statement with an error, `statement_start` is where it began.
+/// The statement ends at its `;`, at the `}` of a block opened inside it or before the
+/// `}` of the block it is in, and before the next statement keyword. A statement that
+/// failed at its first token skips it, so parsing always moves forward.
+fn panic_mode(ctxt: &mut Context, statement_start: usize) {
+    let mut depth = 0;
+
     loop {
-        if ctxt.is_at_end() {
-            break;
-        }
+        let token = ctxt.peek();
+        let first = token.start == statement_start;
 
-        if matches!(ctxt.peek().kind, TokenKind::Semicolon) {
-            ctxt.consume();
-            break;
+        let stop_before = match token.kind {
+            TokenKind::Eof => true,
+            TokenKind::RightBrace => depth == 0 && !first,
+            kind => depth == 0 && !first && RECOVERY_KEYWORDS.contains(&kind),
+        };
+
+        if stop_before {
+            return;
         }
 
         ctxt.consume();
+
+        match token.kind {
+            TokenKind::LeftBrace => depth += 1,
+            TokenKind::RightBrace if depth <= 1 => return,
+            TokenKind::RightBrace => depth -= 1,
+            TokenKind::Semicolon if depth == 0 => return,
+            _ => (),
+        }
     }
 }
 
@@ -131,27 +257,108 @@ impl From<AstWithStatus> for Result<Ast, Box<Ast>> {
     }
 }
 
-pub fn parse(src_id: Source, code: &[u8]) -> Result<Ast, Box<Ast>> {
+/// Syntax errors go to `diagnostics`, parsing goes on after them to find as many as
+/// possible, up to [`MAX_ERRORS`].
+pub fn parse(src_id: Source, code: &[u8], diagnostics: &mut Diagnostics) -> Result<Ast, Box<Ast>> {
     let mut ast = AstWithStatus::default();
     let mut ctxt = Context::new(src_id, code);
 
     ctxt.skip_comments();
 
     while !ctxt.is_at_end() {
+        let statement_start = ctxt.peek().start;
+
         match statement::parse(&mut ctxt, ast.as_mut()) {
             Ok(stmt) => {
                 ast.as_mut().push_into_initial_block(stmt);
                 ctxt.skip_comments();
             }
             Err(error) => {
-                ast.status = AstStatus::Incomplete;
-                rlox_errors::error(error);
-                panic_mode(&mut ctxt);
-
-                continue;
+                ctxt.error(error);
+                panic_mode(&mut ctxt, statement_start);
             }
         }
     }
 
+    ctxt.pending_allows.clear();
+
+    if !ctxt.errors.is_empty() {
+        ast.status = AstStatus::Incomplete;
+    }
+
+    for error in ctxt.errors {
+        diagnostics.error(error);
+    }
+
+    for (code, metadata) in ctxt.allowed {
+        diagnostics.allow_in(code, metadata);
+    }
+
     Result::from(ast)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(code: &str) -> Vec<String> {
+        let mut diagnostics = Diagnostics::new();
+        assert!(parse(Source::Prompt, code.as_bytes(), &mut diagnostics).is_err());
+
+        diagnostics
+            .errors()
+            .map(|error| format!("{} {}", error.code(), error.description()))
+            .collect()
+    }
+
+    #[test]
+    fn statement_keywords_end_the_statement_with_the_error() {
+        assert_eq!(errors("var a = 1\nvar b = 2;\nprintln(a b);\n"), [
+            "E0102 Expected one of the following tokens: [Semicolon], found Var",
+            "E0102 Expected one of the following tokens: [Comma, RightParen], found Identifier",
+        ]);
+    }
+
+    #[test]
+    fn errors_inside_blocks_end_at_the_brace() {
+        assert_eq!(errors("if true {\n    var = 1;\n}\nprintln(1 +);\n}\nprintln(2);\n"), [
+            "E0102 Expected one of the following tokens: [Identifier], found Equal",
+            "E0102 Expected one of the following tokens: [False, True, Nil, Integer, Decimal, String, Identifier, LeftParen, Bang, Minus], found RightParen",
+            "E0102 Expected one of the following tokens: [False, True, Nil, Integer, Decimal, String, Identifier, LeftParen, Bang, Minus], found RightBrace",
+        ]);
+    }
+
+    /// Code of the statements with allowed warnings, after parsing all of `code`.
+    fn allowed(code: &str) -> Vec<(&'static str, &str)> {
+        let mut ctxt = Context::new(Source::Prompt, code.as_bytes());
+        let mut ast = Ast::default();
+
+        ctxt.skip_comments();
+
+        while !ctxt.is_at_end() {
+            statement::parse(&mut ctxt, &mut ast).unwrap();
+            ctxt.skip_comments();
+        }
+
+        ctxt.allowed
+            .into_iter()
+            .map(|(allowed, metadata)| (allowed, code[metadata.start..metadata.end].trim_end()))
+            .collect()
+    }
+
+    #[test]
+    fn allow_comments_stay_in_their_block() {
+        assert_eq!(allowed("{\n    // lox-allow: unused-variable\n    var a = 1;\n}"), [("W0101", "var a = 1;")]);
+        assert!(
+            allowed("var a = 1;\n{\n    println(a);\n    // lox-allow: unused-variable\n}\nvar b = 2;\n").is_empty()
+        );
+    }
+
+    #[test]
+    fn parsing_stops_after_too_many_errors() {
+        let errors = errors(&"var;\n".repeat(2 * MAX_ERRORS));
+
+        assert_eq!(errors.len(), MAX_ERRORS + 1);
+        assert!(errors[MAX_ERRORS].starts_with("E0104"));
+    }
+}
@@ -2,26 +2,72 @@ use rlox_ast::stmt;
 use rlox_ast::stmt::Stmt;
 use rlox_ast::{Ast, AstElem};
 use rlox_infra::StructVec;
+use rlox_infra::similar;
 use rlox_source::SourceMetadata;
 
-use crate::error;
+use crate::error::{self, ParserError};
 use crate::expression;
 use crate::token_stream::TokenKind;
-use crate::{Context, ParserResult};
+use crate::{Context, ParserResult, panic_mode};
+
+/// Keywords that start a statement, an identifier in their place may be a typo.
+pub(crate) const STMT_KEYWORDS: &[&str] = &["var", "if", "while", "for"];
+
+/// Keywords of statements that are not supported yet, still suggested for typos
+/// so the message explains why the statement does not parse.
+const RESERVED_KEYWORDS: &[&str] = &["fun", "return", "class", "print"];
 
 pub fn parse(ctxt: &mut Context, ast: &mut Ast) -> ParserResult<Stmt> {
     stmt(ctxt, ast)
 }
 
+/// Warnings allowed by the comments before the statement are allowed in all of it.
 fn stmt(ctxt: &mut Context, ast: &mut Ast) -> ParserResult<Stmt> {
-    match ctxt.peek().kind {
+    let allowed = std::mem::take(&mut ctxt.pending_allows);
+
+    let stmt = match ctxt.peek().kind {
         TokenKind::Var => var_stmt(ctxt, ast),
         TokenKind::LeftBrace => block_stmt(ctxt, ast),
         TokenKind::If => if_else_stmt(ctxt, ast),
         TokenKind::While => while_stmt(ctxt, ast),
         TokenKind::For => for_stmt(ctxt, ast),
+        TokenKind::Identifier => identifier_stmt(ctxt, ast),
         _ => expr_stmt(ctxt, ast),
-    }
+    }?;
+
+    let metadata = *ast.get(stmt.global_id());
+    ctxt.allowed
+        .extend(allowed.into_iter().map(|code| (code, metadata)));
+
+    Ok(stmt)
+}
+
+/// Statements starting with an identifier are expressions. When the token right after
+/// the identifier is unexpected, the identifier may be a misspelled keyword instead.
+fn identifier_stmt(ctxt: &mut Context, ast: &mut Ast) -> ParserResult<Stmt> {
+    let identifier = ctxt.peek();
+
+    expr_stmt(ctxt, ast).map_err(|error| match error {
+        ParserError::UnexpectedToken(mut unexpected)
+            if ctxt.src[identifier.end..unexpected.start]
+                .iter()
+                .all(u8::is_ascii_whitespace) =>
+        {
+            let name = String::from_utf8_lossy(&ctxt.src[identifier.start..identifier.end]);
+
+            let keywords = STMT_KEYWORDS.iter().chain(RESERVED_KEYWORDS).copied();
+
+            unexpected.misspelling = similar::closest(&name, keywords).map(|keyword| error::Misspelling {
+                start: identifier.start,
+                end: identifier.end,
+                keyword,
+            });
+
+            ParserError::UnexpectedToken(unexpected)
+        }
+
+        error => error,
+    })
 }
 
 fn for_stmt(ctxt: &mut Context, ast: &mut Ast) -> ParserResult<Stmt> {
@@ -29,10 +75,13 @@ fn for_stmt(ctxt: &mut Context, ast: &mut Ast) -> ParserResult<Stmt> {
 
     ctxt.try_consume(TokenKind::LeftParen)?;
 
-    let declaration = if ctxt.match_consume(TokenKind::Semicolon) {
-        None
-    } else {
-        Some(var_stmt(ctxt, ast)?)
+    let declaration = match ctxt.peek().kind {
+        TokenKind::Semicolon => {
+            ctxt.consume();
+            None
+        }
+        TokenKind::Var => Some(var_stmt(ctxt, ast)?),
+        _ => return Err(ctxt.unexpected(vec![TokenKind::Var, TokenKind::Semicolon])),
     };
 
     let condition = if matches!(ctxt.peek().kind, TokenKind::Semicolon) {
@@ -127,12 +176,7 @@ fn else_branch(ctxt: &mut Context, ast: &mut Ast) -> ParserResult<Option<Stmt>>
         TokenKind::If => if_else_stmt(ctxt, ast).map(Some),
         TokenKind::LeftBrace => block_stmt(ctxt, ast).map(Some),
 
-        _ => Err(From::from(error::UnexpectedToken {
-            start: ctxt.peek().start,
-            end: ctxt.peek().end,
-            source: ctxt.src_id,
-            expected: vec![TokenKind::If, TokenKind::LeftBrace],
-        })),
+        _ => Err(ctxt.unexpected(vec![TokenKind::If, TokenKind::LeftBrace])),
     }
 }
 
@@ -201,16 +245,27 @@ fn expr_stmt(ctxt: &mut Context, ast: &mut Ast) -> ParserResult<Stmt> {
     Ok(stmt)
 }
 
+/// Errors in the statements of the block are kept in the context, parsing goes on
+/// with the next statement of the block.
 fn block_stmt(ctxt: &mut Context, ast: &mut Ast) -> ParserResult<Stmt> {
-    let start_token = ctxt.consume();
+    let start_token = ctxt.try_consume(TokenKind::LeftBrace)?;
 
     let mut block_stmts = vec![];
 
     while !matches!(ctxt.peek().kind, TokenKind::Eof | TokenKind::RightBrace) {
-        let block_stmt = stmt(ctxt, ast)?;
-        block_stmts.push(block_stmt);
+        let statement_start = ctxt.peek().start;
+
+        match stmt(ctxt, ast) {
+            Ok(block_stmt) => block_stmts.push(block_stmt),
+            Err(error) => {
+                ctxt.error(error);
+                panic_mode(ctxt, statement_start);
+            }
+        }
     }
 
+    // A comment at the end of the block has no next statement in it.
+    ctxt.pending_allows.clear();
     ctxt.try_consume(TokenKind::RightBrace)?;
 
     let stmt = ast.add(block_stmts.as_slice());
@@ -255,4 +310,20 @@ mod tests {
         assert_eq!(metadata.source, Source::Prompt);
         assert_eq!(&source[metadata.start..metadata.end], source);
     }
+
+    #[test_case(b"whlie x < 1 { }", Some(("while", true)); "misspelled while")]
+    #[test_case(b"retrun 1;", Some(("return", false)); "misspelled return")]
+    #[test_case(b"foo(1 2);", None; "error after the identifier")]
+    fn misspelled_keywords_are_suggested(source: &[u8], expected: Option<(&str, bool)>) {
+        let mut ctxt = Context::new(Source::Prompt, source);
+        let Err(ParserError::UnexpectedToken(error)) = parse(&mut ctxt, &mut Ast::default()) else {
+            panic!("the statement should not parse");
+        };
+
+        let found = error
+            .misspelling
+            .map(|misspelling| (misspelling.keyword, misspelling.is_supported()));
+
+        assert_eq!(found, expected);
+    }
 }
@@ -94,13 +94,18 @@ impl Iterator for TokenStream<'_> {
 }
 
 impl TokenStream<'_> {
-    pub fn new(src: &[u8]) -> TokenStream {
+    pub fn new(src: &[u8]) -> TokenStream<'_> {
         TokenStream {
             src,
             current: 0,
         }
     }
 
+    /// Leaves the rest of the source unread, the next token is the end of file.
+    pub fn skip_to_end(&mut self) {
+        self.current = self.src.len();
+    }
+
     pub fn next_token(&mut self) -> Token {
         while self.current().as_ref().is_some_and(u8::is_ascii_whitespace) {
             self.current += 1;
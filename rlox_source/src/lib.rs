@@ -1,3 +1,5 @@
+/// Where some code comes from. Lines typed in the prompt are files too, named
+/// `<repl:N>`, `Prompt` is for code that is not kept anywhere.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Source {
     Prompt,
@@ -17,6 +19,48 @@ pub struct SourceFile {
     pub data: String,
 }
 
+/// Line and column of a byte offset, both starting at one. Columns count characters, not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourceFile {
+    pub fn position(&self, offset: usize) -> Position {
+        let before = &self.data[..self.line_start(offset)];
+        let line_start = before.len();
+        let offset = offset.min(self.data.len());
+
+        Position {
+            line: 1 + before.matches('\n').count(),
+            column: 1 + String::from_utf8_lossy(&self.data.as_bytes()[line_start..offset])
+                .chars()
+                .count(),
+        }
+    }
+
+    /// Offset where the line containing `offset` starts.
+    pub fn line_start(&self, offset: usize) -> usize {
+        let offset = offset.min(self.data.len());
+
+        self.data.as_bytes()[..offset]
+            .iter()
+            .rposition(|b| *b == b'\n')
+            .map_or(0, |newline| newline + 1)
+    }
+
+    /// Offset of the end of the line containing `offset`, without the line break.
+    pub fn line_end(&self, offset: usize) -> usize {
+        let offset = offset.min(self.data.len());
+
+        self.data.as_bytes()[offset..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map_or(self.data.len(), |newline| offset + newline)
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct SourceLibrary {
     source: Vec<SourceFile>,
@@ -48,3 +92,31 @@ impl SourceLibrary {
         src_id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_count_lines_and_characters() {
+        let file = SourceFile {
+            path: "test.lox".into(),
+            data: "var a = 1;\nprintln(\"é\", a);\n".into(),
+        };
+
+        assert_eq!(file.position(0), Position {
+            line: 1,
+            column: 1,
+        });
+        assert_eq!(file.position(11), Position {
+            line: 2,
+            column: 1,
+        });
+        // The accented character takes two bytes but a single column.
+        assert_eq!(file.position(25), Position {
+            line: 2,
+            column: 14,
+        });
+        assert_eq!((file.line_start(15), file.line_end(15)), (11, 28));
+    }
+}
@@ -0,0 +1,257 @@
+use rlox_ast::expr::BinaryOperator;
+use rlox_interpreter::Value;
+use rlox_source::SourceMetadata;
+
+/// Instructions understood by the vm. Operands are stored right after
+/// the opcode, little endian, with the width given by [`OpCode::operand_width`].
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OpCode {
+    /// Pushes the constant at the given index of the constant pool.
+    Constant,
+    Nil,
+    True,
+    False,
+    Pop,
+    /// Pops the given number of values, used when a scope ends.
+    PopN,
+    /// Pushes a copy of the local stored at the given slot.
+    GetLocal,
+    /// Stores the top of the stack in the given slot and replaces it with nil.
+    SetLocal,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulus,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    And,
+    Or,
+    /// Jumps forward, keeping the top of the stack, if it is `false`.
+    ShortCircuitAnd,
+    /// Jumps forward, keeping the top of the stack, if it is `true`.
+    ShortCircuitOr,
+    Negate,
+    Not,
+    Jump,
+    /// Pops a boolean and jumps forward if it is `false`.
+    JumpIfFalse,
+    /// Jumps backwards.
+    Loop,
+    /// Fails if the top of the stack is not a function.
+    Callable,
+    /// Calls the function below the given number of arguments.
+    Call,
+    /// Fails with an unknown variable error. The operand is the constant with the
+    /// closest name in scope, or nil if none is close.
+    Undefined,
+    /// Fails with an invalid assignment error.
+    InvalidAssign,
+    Return,
+}
+
+#[rustfmt::skip]
+const OPCODES: &[OpCode] = &[
+    OpCode::Constant, OpCode::Nil, OpCode::True, OpCode::False, OpCode::Pop, OpCode::PopN,
+    OpCode::GetLocal, OpCode::SetLocal, OpCode::Add, OpCode::Subtract, OpCode::Multiply,
+    OpCode::Divide, OpCode::Modulus, OpCode::Equal, OpCode::NotEqual, OpCode::Greater,
+    OpCode::GreaterOrEqual, OpCode::Less, OpCode::LessOrEqual, OpCode::And, OpCode::Or,
+    OpCode::ShortCircuitAnd, OpCode::ShortCircuitOr, OpCode::Negate, OpCode::Not, OpCode::Jump,
+    OpCode::JumpIfFalse, OpCode::Loop, OpCode::Callable, OpCode::Call, OpCode::Undefined,
+    OpCode::InvalidAssign, OpCode::Return,
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct InvalidOpCode(pub u8);
+
+impl TryFrom<u8> for OpCode {
+    type Error = InvalidOpCode;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        OPCODES
+            .get(value as usize)
+            .copied()
+            .ok_or(InvalidOpCode(value))
+    }
+}
+
+impl From<BinaryOperator> for OpCode {
+    fn from(value: BinaryOperator) -> Self {
+        match value {
+            BinaryOperator::Division => OpCode::Divide,
+            BinaryOperator::Equal => OpCode::Equal,
+            BinaryOperator::Greater => OpCode::Greater,
+            BinaryOperator::GreaterOrEqual => OpCode::GreaterOrEqual,
+            BinaryOperator::Less => OpCode::Less,
+            BinaryOperator::LessOrEqual => OpCode::LessOrEqual,
+            BinaryOperator::LogicAnd => OpCode::And,
+            BinaryOperator::LogicOr => OpCode::Or,
+            BinaryOperator::Minus => OpCode::Subtract,
+            BinaryOperator::Modulus => OpCode::Modulus,
+            BinaryOperator::Multiply => OpCode::Multiply,
+            BinaryOperator::NotEqual => OpCode::NotEqual,
+            BinaryOperator::Plus => OpCode::Add,
+        }
+    }
+}
+
+impl OpCode {
+    pub fn operand_width(self) -> usize {
+        match self {
+            OpCode::Call => 1,
+
+            OpCode::Constant
+            | OpCode::PopN
+            | OpCode::GetLocal
+            | OpCode::SetLocal
+            | OpCode::ShortCircuitAnd
+            | OpCode::ShortCircuitOr
+            | OpCode::Jump
+            | OpCode::JumpIfFalse
+            | OpCode::Loop
+            | OpCode::Undefined => 2,
+
+            _ => 0,
+        }
+    }
+
+    /// Instructions that can fail when run, they need a span to report the error.
+    pub fn can_fail(self) -> bool {
+        let can_fail = matches!(
+            self,
+            OpCode::Negate
+                | OpCode::Not
+                | OpCode::JumpIfFalse
+                | OpCode::Callable
+                | OpCode::Call
+                | OpCode::Undefined
+                | OpCode::InvalidAssign
+        );
+
+        can_fail || self.binary_operator().is_some()
+    }
+
+    pub fn binary_operator(self) -> Option<BinaryOperator> {
+        match self {
+            OpCode::Divide => Some(BinaryOperator::Division),
+            OpCode::Equal => Some(BinaryOperator::Equal),
+            OpCode::Greater => Some(BinaryOperator::Greater),
+            OpCode::GreaterOrEqual => Some(BinaryOperator::GreaterOrEqual),
+            OpCode::Less => Some(BinaryOperator::Less),
+            OpCode::LessOrEqual => Some(BinaryOperator::LessOrEqual),
+            OpCode::And => Some(BinaryOperator::LogicAnd),
+            OpCode::Or => Some(BinaryOperator::LogicOr),
+            OpCode::Subtract => Some(BinaryOperator::Minus),
+            OpCode::Modulus => Some(BinaryOperator::Modulus),
+            OpCode::Multiply => Some(BinaryOperator::Multiply),
+            OpCode::NotEqual => Some(BinaryOperator::NotEqual),
+            OpCode::Add => Some(BinaryOperator::Plus),
+            _ => None,
+        }
+    }
+}
+
+/// Links the instruction starting at `offset` with the code that generated it.
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    pub offset: usize,
+    pub metadata: SourceMetadata,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Chunk {
+    pub(crate) code: Vec<u8>,
+    pub(crate) constants: Vec<Value>,
+    pub(crate) spans: Vec<Span>,
+}
+
+impl Chunk {
+    pub fn new() -> Chunk {
+        Chunk::default()
+    }
+
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    pub fn constants(&self) -> &[Value] {
+        &self.constants
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    pub fn write_op(&mut self, op: OpCode) -> usize {
+        let offset = self.code.len();
+        self.code.push(op as u8);
+
+        offset
+    }
+
+    pub fn write_u8(&mut self, operand: u8) {
+        self.code.push(operand);
+    }
+
+    pub fn write_u16(&mut self, operand: u16) {
+        self.code.extend_from_slice(&operand.to_le_bytes());
+    }
+
+    pub fn patch_u16(&mut self, offset: usize, operand: u16) {
+        self.code[offset..offset + 2].copy_from_slice(&operand.to_le_bytes());
+    }
+
+    pub fn read_u16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.code[offset], self.code[offset + 1]])
+    }
+
+    /// Spans must be added in increasing offset order.
+    pub fn add_span(&mut self, offset: usize, metadata: SourceMetadata) {
+        self.spans.push(Span {
+            offset,
+            metadata,
+        });
+    }
+
+    pub fn add_constant(&mut self, value: Value) -> usize {
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    /// Metadata of the instruction starting at `offset`, if it has any.
+    pub fn metadata(&self, offset: usize) -> Option<SourceMetadata> {
+        let index = self
+            .spans
+            .binary_search_by_key(&offset, |span| span.offset)
+            .ok()?;
+
+        Some(self.spans[index].metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opcodes_table_matches_discriminants() {
+        for (byte, op) in OPCODES.iter().copied().enumerate() {
+            assert_eq!(op as usize, byte);
+        }
+
+        assert_eq!(OPCODES.last().copied(), Some(OpCode::Return));
+    }
+}
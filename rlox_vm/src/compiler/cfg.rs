@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use rlox_ast::Ast;
+use rlox_cf_graph::{BasicBlock, BasicBlockId, BasicBlockValue, ControlFlowGraph, EdgeKind, Edges, Terminator};
+use rlox_infra::StructVec;
+use rlox_source::SourceMetadata;
+
+use crate::CompileResult;
+use crate::chunk::{Chunk, OpCode};
+use crate::compiler::{Compiler, expression, statement};
+
+struct Lowering<'a> {
+    cf_graph: &'a ControlFlowGraph,
+    /// Order in which the basic blocks are placed in the chunk.
+    layout: Vec<BasicBlockId>,
+    /// Offset in the chunk where each placed basic block starts.
+    starts: HashMap<BasicBlockId, usize>,
+    /// Forward jumps waiting for their target to be placed.
+    pending: Vec<(usize, BasicBlockId, SourceMetadata)>,
+    /// Metadata of the last statement, used for the jumps
+    /// of blocks that end without a condition.
+    metadata: Option<SourceMetadata>,
+}
+
+/// Generates code following the edges of the graph instead of the structure of
+/// the AST. Basic blocks are placed in the order they were created, which is the
+/// order of the statements in the source, with the exit point at the very end.
+/// Jumps are only emitted when a block is not followed by its successor.
+pub fn compile_cfg(cf_graph: &ControlFlowGraph, ast: &Ast) -> CompileResult<Chunk> {
+    let mut compiler = Compiler::new(ast);
+
+    let exit_point = cf_graph.exit_point();
+    let mut layout: Vec<_> = cf_graph
+        .basic_block_ids()
+        .filter(|bb_id| *bb_id != exit_point)
+        .collect();
+
+    layout.push(exit_point);
+
+    let mut lowering = Lowering {
+        cf_graph,
+        layout,
+        starts: HashMap::new(),
+        pending: Vec::new(),
+        metadata: None,
+    };
+
+    for position in 0..lowering.layout.len() {
+        basic_block(position, &mut lowering, &mut compiler)?;
+    }
+
+    for (operand, target, metadata) in std::mem::take(&mut lowering.pending) {
+        compiler.patch_jump_to(operand, lowering.starts[&target], metadata)?;
+    }
+
+    Ok(compiler.chunk)
+}
+
+fn basic_block(position: usize, lowering: &mut Lowering, compiler: &mut Compiler) -> CompileResult<()> {
+    let ast = compiler.ast;
+    let bb_id = lowering.layout[position];
+    let basic_block: &BasicBlock = lowering.cf_graph.get(bb_id);
+
+    lowering.starts.insert(bb_id, compiler.chunk.len());
+
+    for stmt in basic_block.stmts.iter().copied() {
+        match stmt {
+            BasicBlockValue::EnterBlock(_) => compiler.enter_scope(),
+
+            BasicBlockValue::LeaveBlock(_) => compiler.leave_scope(),
+
+            BasicBlockValue::Declaration(node) => {
+                lowering.metadata = Some(*ast.get(node.stmt_id));
+                statement::declaration(node, compiler)?;
+            }
+
+            BasicBlockValue::StmtExpr(node) => {
+                lowering.metadata = Some(*ast.get(node.stmt_id));
+                statement::expr_stmt(node, compiler)?;
+            }
+        }
+    }
+
+    match basic_block.terminator {
+        Terminator::Exit => {
+            compiler.chunk.write_op(OpCode::Return);
+            Ok(())
+        }
+
+        Terminator::Jump => {
+            let edges: &Edges = lowering.cf_graph.get(bb_id);
+            let target: BasicBlockId = *edges.get(0);
+
+            goto(position, target, lowering, compiler)
+        }
+
+        Terminator::Branch(node) => {
+            lowering.metadata = Some(*ast.get(node.stmt_id));
+            expression::compile(node.inner, compiler)?;
+
+            let condition_metadata = statement::condition_metadata(node.stmt_id, node.inner, compiler);
+            branch(position, condition_metadata, lowering, compiler)
+        }
+    }
+}
+
+/// Emits the jumps leaving a condition, whose value is on top of the stack.
+fn branch(
+    position: usize,
+    condition_metadata: SourceMetadata,
+    lowering: &mut Lowering,
+    compiler: &mut Compiler,
+) -> CompileResult<()> {
+    let bb_id = lowering.layout[position];
+    let edges: &Edges = lowering.cf_graph.get(bb_id);
+
+    let mut true_target = None;
+    let mut false_target = None;
+
+    for index in 0..edges.len() {
+        let target: BasicBlockId = *edges.get(index);
+
+        match *edges.get(index) {
+            EdgeKind::True => true_target = Some(target),
+            EdgeKind::False => false_target = Some(target),
+            EdgeKind::Unconditional => unreachable!("conditions only have true and false edges"),
+        }
+    }
+
+    let (Some(true_target), Some(false_target)) = (true_target, false_target) else {
+        unreachable!("conditions always have a true and a false edge")
+    };
+
+    let false_jump = compiler.emit_jump(OpCode::JumpIfFalse, condition_metadata);
+
+    let Some(&false_start) = lowering.starts.get(&false_target) else {
+        lowering
+            .pending
+            .push((false_jump, false_target, condition_metadata));
+        return goto(position, true_target, lowering, compiler);
+    };
+
+    // Conditional jumps only go forward, so going back to an already placed
+    // block requires landing in a loop instruction after the true branch.
+    jump(true_target, condition_metadata, lowering, compiler)?;
+    compiler.patch_jump(false_jump, condition_metadata)?;
+    compiler.emit_loop(false_start, condition_metadata)
+}
+
+/// Continues the execution in `target`, falling through when it is the next block.
+fn goto(position: usize, target: BasicBlockId, lowering: &mut Lowering, compiler: &mut Compiler) -> CompileResult<()> {
+    if lowering.layout.get(position + 1) == Some(&target) {
+        return Ok(());
+    }
+
+    let metadata = lowering
+        .metadata
+        .expect("only blocks after a condition need to jump");
+    jump(target, metadata, lowering, compiler)
+}
+
+fn jump(
+    target: BasicBlockId,
+    metadata: SourceMetadata,
+    lowering: &mut Lowering,
+    compiler: &mut Compiler,
+) -> CompileResult<()> {
+    if let Some(&start) = lowering.starts.get(&target) {
+        return compiler.emit_loop(start, metadata);
+    }
+
+    let operand = compiler.emit_jump(OpCode::Jump, metadata);
+    lowering.pending.push((operand, target, metadata));
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use rlox_ast::Identifier;
+use rlox_ast::expr::*;
+use rlox_infra::StructVec;
+use rlox_interpreter::Value;
+use rlox_source::SourceMetadata;
+
+use crate::CompileResult;
+use crate::chunk::OpCode;
+use crate::compiler::Compiler;
+use crate::error;
+
+type ExprResult = CompileResult<()>;
+
+pub fn compile(expr: Expr, compiler: &mut Compiler) -> ExprResult {
+    let ast = compiler.ast;
+    let metadata = *ast.get(expr.global_id());
+
+    match expr.kind() {
+        ExprKind::Nil => {
+            compiler.emit(OpCode::Nil, metadata);
+            Ok(())
+        }
+        ExprKind::Boolean(true) => {
+            compiler.emit(OpCode::True, metadata);
+            Ok(())
+        }
+        ExprKind::Boolean(false) => {
+            compiler.emit(OpCode::False, metadata);
+            Ok(())
+        }
+        ExprKind::Decimal(inner) => compiler.emit_constant(Value::Decimal(inner), metadata),
+        ExprKind::Natural(inner) => compiler.emit_constant(Value::Natural(inner), metadata),
+        ExprKind::String(inner) => compiler.emit_constant(Value::String(ast[inner].into()), metadata),
+
+        ExprKind::Binary(inner) => binary(expr_node!(expr, inner), compiler),
+        ExprKind::Unary(inner) => unary(expr_node!(expr, inner), compiler),
+        ExprKind::Identifier(inner) => identifier(expr_node!(expr, inner), compiler),
+        ExprKind::Assign(inner) => assign(expr_node!(expr, inner), compiler),
+        ExprKind::Call(inner) => call(expr_node!(expr, inner), compiler),
+    }
+}
+
+fn assign(node: ExprNode<AssignId>, compiler: &mut Compiler) -> ExprResult {
+    let ast = compiler.ast;
+    let assign = &ast[node.inner];
+
+    // Only variables are memory locations, any other left hand side is
+    // still evaluated before failing, as the tree-walk interpreter does.
+    let ExprKind::Identifier(identifier) = assign.lhs.kind() else {
+        compile(assign.lhs, compiler)?;
+        compiler.emit(OpCode::InvalidAssign, *ast.get(node.expr_id));
+
+        return Ok(());
+    };
+
+    let Some(slot) = compiler.resolve(&ast[identifier]) else {
+        return compiler.emit_undefined(&ast[identifier], *ast.get(assign.lhs.global_id()));
+    };
+
+    compile(assign.rhs, compiler)?;
+    compiler.emit(OpCode::SetLocal, *ast.get(node.expr_id));
+    compiler.chunk.write_u16(slot);
+
+    Ok(())
+}
+
+fn identifier(node: ExprNode<Identifier>, compiler: &mut Compiler) -> ExprResult {
+    let ast = compiler.ast;
+    let metadata = *ast.get(node.expr_id);
+
+    // Unknown variables are a runtime error, they only fail if executed.
+    let Some(slot) = compiler.resolve(&ast[node.inner]) else {
+        return compiler.emit_undefined(&ast[node.inner], metadata);
+    };
+
+    compiler.emit(OpCode::GetLocal, metadata);
+    compiler.chunk.write_u16(slot);
+
+    Ok(())
+}
+
+fn binary(node: ExprNode<BinaryId>, compiler: &mut Compiler) -> ExprResult {
+    let ast = compiler.ast;
+    let binary = &ast[node.inner];
+    let metadata = *ast.get(node.expr_id);
+
+    compile(binary.lhs, compiler)?;
+
+    let short_circuit = match binary.operator {
+        BinaryOperator::LogicAnd => Some(compiler.emit_jump(OpCode::ShortCircuitAnd, metadata)),
+        BinaryOperator::LogicOr => Some(compiler.emit_jump(OpCode::ShortCircuitOr, metadata)),
+        _ => None,
+    };
+
+    compile(binary.rhs, compiler)?;
+    compiler.emit(OpCode::from(binary.operator), metadata);
+
+    match short_circuit {
+        Some(jump) => compiler.patch_jump(jump, metadata),
+        None => Ok(()),
+    }
+}
+
+fn unary(node: ExprNode<UnaryId>, compiler: &mut Compiler) -> ExprResult {
+    let ast = compiler.ast;
+    let unary = &ast[node.inner];
+
+    compile(unary.operand, compiler)?;
+
+    let op = match unary.operator {
+        UnaryOperator::Minus => OpCode::Negate,
+        UnaryOperator::Negation => OpCode::Not,
+    };
+
+    compiler.emit(op, *ast.get(node.expr_id));
+
+    Ok(())
+}
+
+fn call(node: ExprNode<CallId>, compiler: &mut Compiler) -> ExprResult {
+    let ast = compiler.ast;
+    let call = &ast[node.inner];
+    let metadata: SourceMetadata = *ast.get(node.expr_id);
+
+    let Ok(arguments) = u8::try_from(call.arguments.len()) else {
+        return Err(From::from(error::TooManyArguments {
+            start: metadata.start,
+            end: metadata.end,
+            source: metadata.source,
+        }));
+    };
+
+    // The callee is checked before evaluating any argument.
+    compile(call.lhs, compiler)?;
+    compiler.emit(OpCode::Callable, metadata);
+
+    for arg in call.arguments.iter().copied() {
+        compile(arg, compiler)?;
+    }
+
+    compiler.emit(OpCode::Call, metadata);
+    compiler.chunk.write_u8(arguments);
+
+    Ok(())
+}
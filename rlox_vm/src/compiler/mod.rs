@@ -0,0 +1,204 @@
+mod cfg;
+mod expression;
+mod statement;
+
+use rlox_ast::Ast;
+use rlox_infra::similar;
+use rlox_interpreter::Value;
+use rlox_interpreter::native_functions;
+use rlox_source::SourceMetadata;
+
+use crate::CompileResult;
+use crate::chunk::{Chunk, OpCode};
+use crate::error;
+
+pub use cfg::compile_cfg;
+
+/// Variables live in the stack, in the slot given by their position in `locals`.
+struct Local<'a> {
+    name: &'a str,
+    depth: usize,
+}
+
+pub struct Compiler<'a> {
+    ast: &'a Ast,
+    chunk: Chunk,
+    locals: Vec<Local<'a>>,
+    depth: usize,
+}
+
+pub fn compile(ast: &Ast) -> CompileResult<Chunk> {
+    let mut compiler = Compiler::new(ast);
+
+    for stmt in ast.main().iter().copied() {
+        statement::compile(stmt, &mut compiler)?;
+    }
+
+    compiler.chunk.write_op(OpCode::Return);
+
+    Ok(compiler.chunk)
+}
+
+impl<'a> Compiler<'a> {
+    fn new(ast: &'a Ast) -> Compiler<'a> {
+        let mut compiler = Compiler {
+            ast,
+            chunk: Chunk::new(),
+            locals: Vec::new(),
+            depth: 0,
+        };
+
+        // Native functions are the first locals of every program, this way
+        // they can be shadowed and reassigned like any other variable.
+        for native_fn in native_functions::REGISTRY {
+            let constant = compiler.chunk.add_constant(Value::Fn(*native_fn));
+
+            compiler.chunk.write_op(OpCode::Constant);
+            compiler.chunk.write_u16(constant as u16);
+            compiler.locals.push(Local {
+                name: native_fn.name,
+                depth: 0,
+            });
+        }
+
+        compiler
+    }
+
+    fn emit(&mut self, op: OpCode, metadata: SourceMetadata) -> usize {
+        let offset = self.chunk.write_op(op);
+        self.chunk.add_span(offset, metadata);
+
+        offset
+    }
+
+    fn emit_constant(&mut self, value: Value, metadata: SourceMetadata) -> CompileResult<()> {
+        let constant = self.add_constant(value, metadata)?;
+
+        self.emit(OpCode::Constant, metadata);
+        self.chunk.write_u16(constant);
+
+        Ok(())
+    }
+
+    /// Emits the failure of an unknown variable. The bytecode has no names, so the
+    /// closest one in scope is looked for now and kept for the error.
+    fn emit_undefined(&mut self, name: &str, metadata: SourceMetadata) -> CompileResult<()> {
+        let suggestion = similar::closest(name, self.locals.iter().map(|local| local.name))
+            .map_or(Value::Nil, |suggestion| Value::String(suggestion.to_string()));
+
+        let constant = self.add_constant(suggestion, metadata)?;
+
+        self.emit(OpCode::Undefined, metadata);
+        self.chunk.write_u16(constant);
+
+        Ok(())
+    }
+
+    fn add_constant(&mut self, value: Value, metadata: SourceMetadata) -> CompileResult<u16> {
+        let Ok(constant) = u16::try_from(self.chunk.add_constant(value)) else {
+            return Err(From::from(error::TooManyConstants {
+                start: metadata.start,
+                end: metadata.end,
+                source: metadata.source,
+            }));
+        };
+
+        Ok(constant)
+    }
+
+    /// Emits a forward jump, returning the offset of the operand that
+    /// has to be patched once the target is known.
+    fn emit_jump(&mut self, op: OpCode, metadata: SourceMetadata) -> usize {
+        self.emit(op, metadata);
+        self.chunk.write_u16(u16::MAX);
+
+        self.chunk.len() - 2
+    }
+
+    /// Makes the jump whose operand is at `operand` land in the next instruction.
+    fn patch_jump(&mut self, operand: usize, metadata: SourceMetadata) -> CompileResult<()> {
+        self.patch_jump_to(operand, self.chunk.len(), metadata)
+    }
+
+    /// Makes the jump whose operand is at `operand` land in `target`.
+    fn patch_jump_to(&mut self, operand: usize, target: usize, metadata: SourceMetadata) -> CompileResult<()> {
+        let distance = target - (operand + 2);
+
+        let Ok(distance) = u16::try_from(distance) else {
+            return Err(From::from(error::JumpTooLarge {
+                start: metadata.start,
+                end: metadata.end,
+                source: metadata.source,
+            }));
+        };
+
+        self.chunk.patch_u16(operand, distance);
+
+        Ok(())
+    }
+
+    fn emit_loop(&mut self, loop_start: usize, metadata: SourceMetadata) -> CompileResult<()> {
+        self.emit(OpCode::Loop, metadata);
+
+        let Ok(distance) = u16::try_from(self.chunk.len() + 2 - loop_start) else {
+            return Err(From::from(error::JumpTooLarge {
+                start: metadata.start,
+                end: metadata.end,
+                source: metadata.source,
+            }));
+        };
+
+        self.chunk.write_u16(distance);
+
+        Ok(())
+    }
+
+    fn resolve(&self, name: &str) -> Option<u16> {
+        let slot = self.locals.iter().rposition(|local| local.name == name)?;
+
+        // Declaring a local already checks that its slot fits in an u16.
+        Some(slot as u16)
+    }
+
+    /// Registers the value on top of the stack as a new local.
+    fn declare(&mut self, name: &'a str, metadata: SourceMetadata) -> CompileResult<()> {
+        if self.locals.len() > u16::MAX as usize {
+            return Err(From::from(error::TooManyLocals {
+                start: metadata.start,
+                end: metadata.end,
+                source: metadata.source,
+            }));
+        }
+
+        self.locals.push(Local {
+            name,
+            depth: self.depth,
+        });
+
+        Ok(())
+    }
+
+    fn enter_scope(&mut self) {
+        self.depth += 1;
+    }
+
+    /// Pops the locals of the scope, this can not fail so the instruction has no span.
+    fn leave_scope(&mut self) {
+        self.depth -= 1;
+
+        let alive = self
+            .locals
+            .iter()
+            .rposition(|local| local.depth <= self.depth)
+            .map_or(0, |last| last + 1);
+
+        let dead = self.locals.len() - alive;
+        self.locals.truncate(alive);
+
+        if dead > 0 {
+            self.chunk.write_op(OpCode::PopN);
+            // Every dead local had a slot, so their count fits in an u16.
+            self.chunk.write_u16(dead as u16);
+        }
+    }
+}
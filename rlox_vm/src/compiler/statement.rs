@@ -0,0 +1,110 @@
+use rlox_ast::expr::Expr;
+use rlox_ast::stmt::*;
+use rlox_infra::StructVec;
+use rlox_source::SourceMetadata;
+
+use crate::CompileResult;
+use crate::chunk::OpCode;
+use crate::compiler::{Compiler, expression};
+
+type StmtResult = CompileResult<()>;
+
+pub fn compile(stmt: Stmt, compiler: &mut Compiler) -> StmtResult {
+    match stmt.kind() {
+        StmtKind::Expr(inner) => expr_stmt(stmt_node!(stmt, inner), compiler),
+        StmtKind::Declaration(inner) => declaration(stmt_node!(stmt, inner), compiler),
+        StmtKind::Block(inner) => block(stmt_node!(stmt, inner), compiler),
+        StmtKind::IfElse(inner) => if_else(stmt_node!(stmt, inner), compiler),
+        StmtKind::While(inner) => while_stmt(stmt_node!(stmt, inner), compiler),
+    }
+}
+
+/// Conditions report errors from the start of their statement, as the tree-walk interpreter does.
+pub fn condition_metadata(stmt: StmtId, condition: Expr, compiler: &Compiler) -> SourceMetadata {
+    let stmt_metadata: &SourceMetadata = compiler.ast.get(stmt);
+    let condition_metadata: &SourceMetadata = compiler.ast.get(condition.global_id());
+
+    SourceMetadata {
+        start: stmt_metadata.start,
+        end: condition_metadata.end,
+        source: stmt_metadata.source,
+    }
+}
+
+pub fn declaration(node: StmtNode<DeclarationId>, compiler: &mut Compiler) -> StmtResult {
+    let ast = compiler.ast;
+    let declaration = &ast[node.inner];
+    let metadata = *ast.get(node.stmt_id);
+
+    match declaration.value {
+        None => {
+            compiler.emit(OpCode::Nil, metadata);
+        }
+        Some(expr) => expression::compile(expr, compiler)?,
+    };
+
+    compiler.declare(&ast[declaration.identifier], metadata)
+}
+
+fn block(node: StmtNode<BlockId>, compiler: &mut Compiler) -> StmtResult {
+    let ast = compiler.ast;
+    let block = &ast[node.inner];
+
+    compiler.enter_scope();
+
+    for stmt in block.iter().copied() {
+        compile(stmt, compiler)?;
+    }
+
+    compiler.leave_scope();
+
+    Ok(())
+}
+
+fn if_else(node: StmtNode<IfElseId>, compiler: &mut Compiler) -> StmtResult {
+    let ast = compiler.ast;
+    let stmt = &ast[node.inner];
+    let metadata = *ast.get(node.stmt_id);
+    let condition_metadata = condition_metadata(node.stmt_id, stmt.condition, compiler);
+
+    expression::compile(stmt.condition, compiler)?;
+    let else_jump = compiler.emit_jump(OpCode::JumpIfFalse, condition_metadata);
+
+    compile(stmt.if_branch, compiler)?;
+
+    let Some(else_branch) = stmt.else_branch else {
+        return compiler.patch_jump(else_jump, metadata);
+    };
+
+    let end_jump = compiler.emit_jump(OpCode::Jump, metadata);
+    compiler.patch_jump(else_jump, metadata)?;
+
+    compile(else_branch, compiler)?;
+    compiler.patch_jump(end_jump, metadata)
+}
+
+fn while_stmt(node: StmtNode<WhileId>, compiler: &mut Compiler) -> StmtResult {
+    let ast = compiler.ast;
+    let stmt = &ast[node.inner];
+    let metadata = *ast.get(node.stmt_id);
+    let condition_metadata = condition_metadata(node.stmt_id, stmt.condition, compiler);
+
+    let loop_start = compiler.chunk.len();
+
+    expression::compile(stmt.condition, compiler)?;
+    let exit_jump = compiler.emit_jump(OpCode::JumpIfFalse, condition_metadata);
+
+    compile(stmt.body, compiler)?;
+    compiler.emit_loop(loop_start, metadata)?;
+
+    compiler.patch_jump(exit_jump, metadata)
+}
+
+pub fn expr_stmt(node: StmtNode<Expr>, compiler: &mut Compiler) -> StmtResult {
+    let metadata = *compiler.ast.get(node.stmt_id);
+
+    expression::compile(node.inner, compiler)?;
+    compiler.emit(OpCode::Pop, metadata);
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use std::io::{Result, Write};
+
+use rlox_interpreter::Value;
+use rlox_source::{Source, SourceLibrary, SourceMetadata};
+
+use crate::chunk::{Chunk, OpCode};
+
+/// Writes a human readable listing of the chunk. Instructions are annotated
+/// with the source line that generated them when the source is available.
+pub fn disassemble<W: Write>(chunk: &Chunk, library: &SourceLibrary, writer: &mut W) -> Result<()> {
+    writeln!(writer, "constants:")?;
+
+    for (index, constant) in chunk.constants.iter().enumerate() {
+        writeln!(writer, "  {index:>5}  {}", fmt_constant(constant))?;
+    }
+
+    writeln!(writer, "code:")?;
+
+    let mut offset = 0;
+    let mut last_line = None;
+
+    while offset < chunk.len() {
+        if let Some(line) = chunk
+            .metadata(offset)
+            .and_then(|metadata| source_line(metadata, library))
+        {
+            if last_line != Some(line.0) {
+                writeln!(writer, "         ; {:>4}| {}", line.0, line.1)?;
+                last_line = Some(line.0);
+            }
+        }
+
+        offset = instruction(chunk, offset, writer)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the instruction at `offset`, returning the offset of the next one.
+fn instruction<W: Write>(chunk: &Chunk, offset: usize, writer: &mut W) -> Result<usize> {
+    let Ok(op) = OpCode::try_from(chunk.code[offset]) else {
+        writeln!(writer, "  {offset:05}  <invalid {}>", chunk.code[offset])?;
+        return Ok(offset + 1);
+    };
+
+    let next = offset + 1 + op.operand_width();
+    let name = format!("{op:?}");
+
+    match op {
+        OpCode::Constant | OpCode::Undefined => {
+            let constant = chunk.read_u16(offset + 1) as usize;
+            let value = fmt_constant(&chunk.constants[constant]);
+            writeln!(writer, "  {offset:05}  {name:<16} {constant:>5}  ({value})")?;
+        }
+
+        OpCode::Jump | OpCode::JumpIfFalse | OpCode::ShortCircuitAnd | OpCode::ShortCircuitOr => {
+            let target = next + chunk.read_u16(offset + 1) as usize;
+            writeln!(writer, "  {offset:05}  {name:<16} {target:>5}")?;
+        }
+
+        OpCode::Loop => {
+            let target = next - chunk.read_u16(offset + 1) as usize;
+            writeln!(writer, "  {offset:05}  {name:<16} {target:>5}")?;
+        }
+
+        OpCode::PopN | OpCode::GetLocal | OpCode::SetLocal => {
+            let operand = chunk.read_u16(offset + 1);
+            writeln!(writer, "  {offset:05}  {name:<16} {operand:>5}")?;
+        }
+
+        OpCode::Call => {
+            let operand = chunk.code[offset + 1];
+            writeln!(writer, "  {offset:05}  {name:<16} {operand:>5}")?;
+        }
+
+        _ => writeln!(writer, "  {offset:05}  {name}")?,
+    }
+
+    Ok(next)
+}
+
+fn fmt_constant(constant: &Value) -> String {
+    match constant {
+        Value::String(inner) => format!("{inner:?}"),
+        Value::Fn(inner) => format!("<fn {}>", inner.name),
+        other => other.to_string(),
+    }
+}
+
+/// Number and text of the first line of code covered by `metadata`.
+fn source_line(metadata: SourceMetadata, library: &SourceLibrary) -> Option<(usize, &str)> {
+    let Source::File(index) = metadata.source else {
+        return None;
+    };
+
+    let data = library[index].data.as_str();
+    let before = data.get(..metadata.start)?;
+
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let line_end = data[line_start..]
+        .find('\n')
+        .map_or(data.len(), |newline| line_start + newline);
+
+    let line_number = 1 + before.matches('\n').count();
+
+    Some((line_number, &data[line_start..line_end]))
+}
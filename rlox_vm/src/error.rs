@@ -0,0 +1,145 @@
+use rlox_errors::{Error, Message};
+use rlox_source::{Source, SourceMetadata};
+
+#[derive(Debug)]
+pub enum CompileError {
+    TooManyConstants(TooManyConstants),
+    TooManyLocals(TooManyLocals),
+    TooManyArguments(TooManyArguments),
+    JumpTooLarge(JumpTooLarge),
+}
+
+impl From<CompileError> for Error {
+    fn from(value: CompileError) -> Self {
+        match value {
+            CompileError::TooManyConstants(e) => e.into(),
+            CompileError::TooManyLocals(e) => e.into(),
+            CompileError::TooManyArguments(e) => e.into(),
+            CompileError::JumpTooLarge(e) => e.into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TooManyConstants {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) source: Source,
+}
+
+impl From<TooManyConstants> for CompileError {
+    fn from(value: TooManyConstants) -> Self {
+        CompileError::TooManyConstants(value)
+    }
+}
+
+impl Message for TooManyConstants {
+    fn code(&self) -> &'static str {
+        "E0301"
+    }
+
+    fn description(&self) -> String {
+        format!("A program can not have more than {} constants", u16::MAX)
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TooManyLocals {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) source: Source,
+}
+
+impl From<TooManyLocals> for CompileError {
+    fn from(value: TooManyLocals) -> Self {
+        CompileError::TooManyLocals(value)
+    }
+}
+
+impl Message for TooManyLocals {
+    fn code(&self) -> &'static str {
+        "E0302"
+    }
+
+    fn description(&self) -> String {
+        format!("A program can not have more than {} variables alive", u16::MAX)
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TooManyArguments {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) source: Source,
+}
+
+impl From<TooManyArguments> for CompileError {
+    fn from(value: TooManyArguments) -> Self {
+        CompileError::TooManyArguments(value)
+    }
+}
+
+impl Message for TooManyArguments {
+    fn code(&self) -> &'static str {
+        "E0303"
+    }
+
+    fn description(&self) -> String {
+        format!("A call can not have more than {} arguments", u8::MAX)
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct JumpTooLarge {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) source: Source,
+}
+
+impl From<JumpTooLarge> for CompileError {
+    fn from(value: JumpTooLarge) -> Self {
+        CompileError::JumpTooLarge(value)
+    }
+}
+
+impl Message for JumpTooLarge {
+    fn code(&self) -> &'static str {
+        "E0304"
+    }
+
+    fn description(&self) -> String {
+        "Too much code to jump over".into()
+    }
+
+    fn source_metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            start: self.start,
+            end: self.end,
+            source: self.source,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+pub mod chunk;
+pub mod compiler;
+pub mod loxb;
+
+mod disassembler;
+mod error;
+mod vm;
+
+pub use chunk::Chunk;
+pub use compiler::{compile, compile_cfg};
+pub use disassembler::disassemble;
+
+use rlox_ast::Ast;
+use rlox_cf_graph::build_cfg;
+use rlox_errors::Diagnostics;
+use rlox_interpreter::{EvalReport, RuntimeFailure};
+
+type CompileResult<T> = Result<T, error::CompileError>;
+
+/// Compiles the program into bytecode and runs it, failing the same
+/// way the tree-walk interpreter does.
+pub fn eval(ast: &Ast, diagnostics: &mut Diagnostics) -> Result<EvalReport, RuntimeFailure> {
+    let chunk = match compiler::compile(ast) {
+        Ok(chunk) => chunk,
+        Err(error) => {
+            diagnostics.error(error);
+            return Err(RuntimeFailure);
+        }
+    };
+
+    run(&chunk, diagnostics)
+}
+
+/// Same as [`eval`], but the bytecode is generated from the control-flow graph of the program.
+pub fn eval_cfg(ast: &Ast, diagnostics: &mut Diagnostics) -> Result<EvalReport, RuntimeFailure> {
+    let cf_graph = build_cfg::from_sequence_of_stmts(ast.main(), ast);
+
+    let chunk = match compiler::compile_cfg(&cf_graph, ast) {
+        Ok(chunk) => chunk,
+        Err(error) => {
+            diagnostics.error(error);
+            return Err(RuntimeFailure);
+        }
+    };
+
+    run(&chunk, diagnostics)
+}
+
+pub fn run(chunk: &Chunk, diagnostics: &mut Diagnostics) -> Result<EvalReport, RuntimeFailure> {
+    if let Err(error) = vm::run(chunk) {
+        diagnostics.error(error);
+        return Err(RuntimeFailure);
+    }
+
+    Ok(EvalReport)
+}
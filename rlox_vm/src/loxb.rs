@@ -0,0 +1,571 @@
+//! `.loxb` files store a compiled [`Chunk`] so it can run without parsing the program again.
+//!
+//! Every integer is little endian and the layout is:
+//! - Header: the `LOXB` magic followed by the format version as an `u16`.
+//! - Source: path, length and checksum of the code the chunk was compiled from.
+//! - Constant pool: `u32` count followed by tagged values.
+//! - Code: `u32` length followed by the bytecode.
+//! - Line table: `u32` count followed by `(offset, start, end)` triples of `u32`.
+
+use rlox_interpreter::Value;
+use rlox_interpreter::native_functions;
+use rlox_source::{Source, SourceFile, SourceLibrary, SourceMetadata};
+
+use crate::chunk::{Chunk, OpCode, Span};
+
+pub const MAGIC: &[u8; 4] = b"LOXB";
+pub const VERSION: u16 = 2;
+
+const NIL_TAG: u8 = 0;
+const BOOLEAN_TAG: u8 = 1;
+const NATURAL_TAG: u8 = 2;
+const SIGNED_TAG: u8 = 3;
+const DECIMAL_TAG: u8 = 4;
+const STRING_TAG: u8 = 5;
+const FN_TAG: u8 = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    NotBytecode,
+    UnsupportedVersion(u16),
+    Truncated,
+    InvalidUtf8,
+    InvalidConstant(u8),
+    UnknownNativeFn(String),
+    InvalidCode(usize),
+    InvalidLineTable,
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::NotBytecode => write!(f, "not a lox bytecode file"),
+            LoadError::UnsupportedVersion(version) => {
+                write!(f, "unsupported bytecode version {version}, expected {VERSION}")
+            }
+            LoadError::Truncated => write!(f, "the file ends unexpectedly"),
+            LoadError::InvalidUtf8 => write!(f, "a string is not valid UTF-8"),
+            LoadError::InvalidConstant(tag) => write!(f, "unknown constant tag {tag}"),
+            LoadError::UnknownNativeFn(name) => write!(f, "unknown native function {name:?}"),
+            LoadError::InvalidCode(offset) => write!(f, "invalid instruction at offset {offset}"),
+            LoadError::InvalidLineTable => write!(f, "the line table does not match the code"),
+        }
+    }
+}
+
+type LoadResult<T> = Result<T, LoadError>;
+
+/// FNV-1a, used to check that the source found when loading is the one that was compiled.
+fn checksum(data: &[u8]) -> u64 {
+    data.iter()
+        .fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+pub fn encode(chunk: &Chunk, library: &SourceLibrary) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(MAGIC.len() + chunk.len());
+
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+
+    let source = chunk.spans.first().map(|span| span.metadata.source);
+
+    match source {
+        Some(Source::File(index)) => {
+            let file = &library[index];
+            write_bytes(&mut bytes, file.path.as_bytes());
+            write_u32(&mut bytes, file.data.len());
+            bytes.extend_from_slice(&checksum(file.data.as_bytes()).to_le_bytes());
+        }
+        _ => {
+            write_bytes(&mut bytes, &[]);
+            write_u32(&mut bytes, 0);
+            bytes.extend_from_slice(&0_u64.to_le_bytes());
+        }
+    }
+
+    write_u32(&mut bytes, chunk.constants.len());
+
+    for constant in chunk.constants.iter() {
+        encode_constant(&mut bytes, constant);
+    }
+
+    write_bytes(&mut bytes, &chunk.code);
+
+    write_u32(&mut bytes, chunk.spans.len());
+
+    for span in chunk.spans.iter() {
+        write_u32(&mut bytes, span.offset);
+        write_u32(&mut bytes, span.metadata.start);
+        write_u32(&mut bytes, span.metadata.end);
+    }
+
+    bytes
+}
+
+fn encode_constant(bytes: &mut Vec<u8>, constant: &Value) {
+    match constant {
+        Value::Nil => bytes.push(NIL_TAG),
+        Value::Boolean(inner) => bytes.extend_from_slice(&[BOOLEAN_TAG, *inner as u8]),
+        Value::Natural(inner) => {
+            bytes.push(NATURAL_TAG);
+            bytes.extend_from_slice(&inner.to_le_bytes());
+        }
+        Value::Signed(inner) => {
+            bytes.push(SIGNED_TAG);
+            bytes.extend_from_slice(&inner.to_le_bytes());
+        }
+        Value::Decimal(inner) => {
+            bytes.push(DECIMAL_TAG);
+            bytes.extend_from_slice(&inner.to_bits().to_le_bytes());
+        }
+        Value::String(inner) => {
+            bytes.push(STRING_TAG);
+            write_bytes(bytes, inner.as_bytes());
+        }
+        Value::Fn(inner) => {
+            bytes.push(FN_TAG);
+            write_bytes(bytes, inner.name.as_bytes());
+        }
+        Value::Addr(_) => unreachable!("Memory addresses are never constants"),
+    }
+}
+
+fn write_u32(bytes: &mut Vec<u8>, value: usize) {
+    let Ok(value) = u32::try_from(value) else {
+        panic!("{value} does not fit in a bytecode file");
+    };
+
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_bytes(bytes: &mut Vec<u8>, data: &[u8]) {
+    write_u32(bytes, data.len());
+    bytes.extend_from_slice(data);
+}
+
+/// Decodes a chunk. If the source it was compiled from is still available and
+/// unchanged it is added to `library`, so diagnostics can show the code.
+pub fn decode(bytes: &[u8], library: &mut SourceLibrary) -> LoadResult<Chunk> {
+    let mut reader = Reader {
+        bytes,
+        position: 0,
+    };
+
+    if reader.bytes(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+        return Err(LoadError::NotBytecode);
+    }
+
+    let version = reader.u16()?;
+
+    if version != VERSION {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+
+    let path = reader.string()?;
+    let source_len = reader.u32()?;
+    let source_checksum = reader.u64()?;
+
+    let mut chunk = Chunk::new();
+
+    for _ in 0..reader.u32()? {
+        let constant = decode_constant(&mut reader)?;
+        chunk.add_constant(constant);
+    }
+
+    let code_len = reader.u32()?;
+    chunk.code = reader.bytes(code_len)?.to_vec();
+
+    let source = load_source(path, source_len, source_checksum, library);
+
+    for _ in 0..reader.u32()? {
+        let offset = reader.u32()?;
+        let start = reader.u32()?;
+        let end = reader.u32()?;
+
+        let in_order = chunk.spans.last().is_none_or(|last| last.offset < offset);
+
+        if !in_order || offset >= chunk.len() || start > end {
+            return Err(LoadError::InvalidLineTable);
+        }
+
+        chunk.spans.push(Span {
+            offset,
+            metadata: SourceMetadata {
+                start,
+                end,
+                source,
+            },
+        });
+    }
+
+    validate_code(&chunk)?;
+
+    Ok(chunk)
+}
+
+fn load_source(path: String, len: usize, expected: u64, library: &mut SourceLibrary) -> Source {
+    let Ok(data) = std::fs::read_to_string(&path) else {
+        return Source::Prompt;
+    };
+
+    if data.len() != len || checksum(data.as_bytes()) != expected {
+        return Source::Prompt;
+    }
+
+    Source::File(library.add(SourceFile {
+        path,
+        data,
+    }))
+}
+
+fn decode_constant(reader: &mut Reader) -> LoadResult<Value> {
+    match reader.u8()? {
+        NIL_TAG => Ok(Value::Nil),
+        BOOLEAN_TAG => Ok(Value::Boolean(reader.u8()? != 0)),
+        NATURAL_TAG => Ok(Value::Natural(reader.u64()?)),
+        SIGNED_TAG => Ok(Value::Signed(reader.u64()? as i64)),
+        DECIMAL_TAG => Ok(Value::Decimal(f64::from_bits(reader.u64()?))),
+        STRING_TAG => Ok(Value::String(reader.string()?)),
+        FN_TAG => {
+            let name = reader.string()?;
+
+            native_functions::REGISTRY
+                .iter()
+                .find(|native_fn| native_fn.name == name)
+                .map(|native_fn| Value::Fn(*native_fn))
+                .ok_or(LoadError::UnknownNativeFn(name))
+        }
+        tag => Err(LoadError::InvalidConstant(tag)),
+    }
+}
+
+/// Checks that the vm will not read out of the code or the constant pool, that
+/// every instruction finds on the stack the values and locals it uses and that
+/// the ones that can fail have a span.
+fn validate_code(chunk: &Chunk) -> LoadResult<()> {
+    let mut starts = vec![false; chunk.len()];
+    let mut offset = 0;
+
+    while offset < chunk.len() {
+        let Ok(op) = OpCode::try_from(chunk.code[offset]) else {
+            return Err(LoadError::InvalidCode(offset));
+        };
+
+        let next = offset + 1 + op.operand_width();
+
+        if next > chunk.len() {
+            return Err(LoadError::InvalidCode(offset));
+        }
+
+        let has_constant = matches!(op, OpCode::Constant | OpCode::Undefined);
+
+        if has_constant && chunk.read_u16(offset + 1) as usize >= chunk.constants.len() {
+            return Err(LoadError::InvalidCode(offset));
+        }
+
+        if op.can_fail() && chunk.metadata(offset).is_none() {
+            return Err(LoadError::InvalidLineTable);
+        }
+
+        starts[offset] = true;
+        offset = next;
+    }
+
+    match chunk.code.last().copied().map(OpCode::try_from) {
+        Some(Ok(OpCode::Return)) => validate_stack(chunk, &starts),
+        _ => Err(LoadError::InvalidCode(chunk.len().saturating_sub(1))),
+    }
+}
+
+/// Follows every path through the code keeping the stack, which has to be the
+/// same whenever two paths reach the same instruction. Only whether each value
+/// went through `Callable` is known, `Call` relies on it for the callee.
+fn validate_stack(chunk: &Chunk, starts: &[bool]) -> LoadResult<()> {
+    let mut stacks: Vec<Option<Vec<bool>>> = vec![None; chunk.len()];
+    let mut pending = vec![(0, Vec::new())];
+
+    while let Some((offset, mut stack)) = pending.pop() {
+        match &stacks[offset] {
+            Some(known) if *known == stack => continue,
+            Some(_) => return Err(LoadError::InvalidCode(offset)),
+            None => stacks[offset] = Some(stack.clone()),
+        }
+
+        let Ok(op) = OpCode::try_from(chunk.code[offset]) else {
+            return Err(LoadError::InvalidCode(offset));
+        };
+
+        let next = offset + 1 + op.operand_width();
+        let operand = match op.operand_width() {
+            1 => chunk.code[offset + 1] as usize,
+            2 => chunk.read_u16(offset + 1) as usize,
+            _ => 0,
+        };
+
+        let (popped, pushed) = match op {
+            OpCode::Constant | OpCode::Nil | OpCode::True | OpCode::False | OpCode::GetLocal => (0, 1),
+            OpCode::Pop | OpCode::JumpIfFalse => (1, 0),
+            OpCode::PopN => (operand, 0),
+            OpCode::SetLocal | OpCode::Negate | OpCode::Not => (1, 1),
+            OpCode::Callable | OpCode::ShortCircuitAnd | OpCode::ShortCircuitOr => (1, 1),
+            OpCode::Call => (operand + 1, 1),
+            OpCode::Add
+            | OpCode::Subtract
+            | OpCode::Multiply
+            | OpCode::Divide
+            | OpCode::Modulus
+            | OpCode::Equal
+            | OpCode::NotEqual
+            | OpCode::Greater
+            | OpCode::GreaterOrEqual
+            | OpCode::Less
+            | OpCode::LessOrEqual
+            | OpCode::And
+            | OpCode::Or => (2, 1),
+            OpCode::Jump | OpCode::Loop | OpCode::Undefined | OpCode::InvalidAssign | OpCode::Return => (0, 0),
+        };
+
+        let depth = stack.len();
+        let Some(remaining) = depth.checked_sub(popped) else {
+            return Err(LoadError::InvalidCode(offset));
+        };
+
+        // `SetLocal` pops the value before storing it.
+        let is_valid = match op {
+            OpCode::GetLocal => operand < depth,
+            OpCode::SetLocal => operand < remaining,
+            OpCode::Call => stack[remaining],
+            _ => true,
+        };
+
+        if !is_valid {
+            return Err(LoadError::InvalidCode(offset));
+        }
+
+        stack.truncate(remaining);
+        stack.extend(std::iter::repeat_n(op == OpCode::Callable, pushed));
+
+        let targets = match op {
+            OpCode::Jump => vec![Some(next + operand)],
+            OpCode::JumpIfFalse | OpCode::ShortCircuitAnd | OpCode::ShortCircuitOr => {
+                vec![Some(next), Some(next + operand)]
+            }
+            OpCode::Loop => vec![next.checked_sub(operand)],
+            OpCode::Undefined | OpCode::InvalidAssign | OpCode::Return => vec![],
+            _ => vec![Some(next)],
+        };
+
+        for target in targets {
+            match target {
+                Some(target) if starts.get(target) == Some(&true) => pending.push((target, stack.clone())),
+                _ => return Err(LoadError::InvalidCode(offset)),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> LoadResult<&'a [u8]> {
+        let end = self.position.checked_add(len).ok_or(LoadError::Truncated)?;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(LoadError::Truncated)?;
+
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> LoadResult<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> LoadResult<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> LoadResult<usize> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    fn u64(&mut self) -> LoadResult<u64> {
+        let mut buffer = [0; 8];
+        buffer.copy_from_slice(self.bytes(8)?);
+
+        Ok(u64::from_le_bytes(buffer))
+    }
+
+    fn string(&mut self) -> LoadResult<String> {
+        let len = self.u32()?;
+        let bytes = self.bytes(len)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| LoadError::InvalidUtf8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlox_errors::Diagnostics;
+
+    use super::*;
+
+    fn compile(code: &str) -> Chunk {
+        let Ok(ast) = rlox_parser::parse(Source::Prompt, code.as_bytes(), &mut Diagnostics::new()) else {
+            panic!("{code:?} should parse");
+        };
+
+        let Ok(chunk) = crate::compile(&ast) else {
+            panic!("{code:?} should compile");
+        };
+
+        chunk
+    }
+
+    #[test]
+    fn round_trip() {
+        let chunk = compile("var x = 1.5; { var y = \"text\"; println(x > 2 or y == nil); }");
+        let mut library = SourceLibrary::new();
+
+        let decoded = decode(&encode(&chunk, &library), &mut library).unwrap();
+
+        assert_eq!(decoded.code, chunk.code);
+        assert_eq!(format!("{:?}", decoded.constants), format!("{:?}", chunk.constants));
+        assert_eq!(format!("{:?}", decoded.spans), format!("{:?}", chunk.spans));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let mut library = SourceLibrary::new();
+
+        assert_eq!(decode(b"var x = 2;", &mut library).err(), Some(LoadError::NotBytecode));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let chunk = compile("println(1);");
+        let mut library = SourceLibrary::new();
+        let mut bytes = encode(&chunk, &library);
+
+        bytes[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&(VERSION + 1).to_le_bytes());
+
+        assert_eq!(decode(&bytes, &mut library).err(), Some(LoadError::UnsupportedVersion(VERSION + 1)));
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let chunk = compile("var x = 1; x = x + 1;");
+        let mut library = SourceLibrary::new();
+        let bytes = encode(&chunk, &library);
+
+        for len in 0..bytes.len() {
+            assert!(decode(&bytes[..len], &mut library).is_err(), "{len} bytes were accepted");
+        }
+    }
+
+    /// Encodes the program with the operand of its first `op` replaced.
+    fn corrupt(code: &str, op: OpCode, operand: u16) -> Vec<u8> {
+        let mut chunk = compile(code);
+        let mut offset = 0;
+
+        while chunk.code[offset] != op as u8 {
+            offset += 1 + OpCode::try_from(chunk.code[offset])
+                .unwrap()
+                .operand_width();
+        }
+
+        match op.operand_width() {
+            1 => chunk.code[offset + 1] = operand as u8,
+            _ => chunk.code[offset + 1..offset + 3].copy_from_slice(&operand.to_le_bytes()),
+        }
+
+        encode(&chunk, &SourceLibrary::new())
+    }
+
+    #[test]
+    fn rejects_locals_out_of_the_stack() {
+        let mut library = SourceLibrary::new();
+
+        for op in [OpCode::GetLocal, OpCode::SetLocal] {
+            let bytes = corrupt("var x = 1; x = x + 1;", op, 255);
+            assert!(matches!(decode(&bytes, &mut library), Err(LoadError::InvalidCode(_))), "{op:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_stack_underflows() {
+        let mut library = SourceLibrary::new();
+
+        let bytes = corrupt("{ var x = 1; }", OpCode::PopN, 255);
+        assert!(matches!(decode(&bytes, &mut library), Err(LoadError::InvalidCode(_))));
+
+        let bytes = corrupt("println(1);", OpCode::Call, 255);
+        assert!(matches!(decode(&bytes, &mut library), Err(LoadError::InvalidCode(_))));
+    }
+
+    #[test]
+    fn rejects_jumps_into_operands() {
+        let mut library = SourceLibrary::new();
+
+        let bytes = corrupt("if true { println(1); }", OpCode::JumpIfFalse, 1);
+        assert!(matches!(decode(&bytes, &mut library), Err(LoadError::InvalidCode(_))));
+
+        let bytes = corrupt("var i = 0; while i < 2 { i = i + 1; }", OpCode::Loop, 2);
+        assert!(matches!(decode(&bytes, &mut library), Err(LoadError::InvalidCode(_))));
+    }
+
+    /// Encodes the instructions with the given spans, without going through the compiler.
+    fn hand_built(ops: &[OpCode], constants: Vec<Value>, with_spans: bool) -> Vec<u8> {
+        let mut chunk = Chunk::new();
+
+        for constant in constants {
+            chunk.add_constant(constant);
+        }
+
+        for op in ops {
+            let offset = chunk.write_op(*op);
+
+            if with_spans {
+                chunk.add_span(offset, SourceMetadata {
+                    start: 0,
+                    end: 0,
+                    source: Source::Prompt,
+                });
+            }
+
+            match op.operand_width() {
+                1 => chunk.code.push(0),
+                2 => chunk.write_u16(0),
+                _ => (),
+            }
+        }
+
+        encode(&chunk, &SourceLibrary::new())
+    }
+
+    #[test]
+    fn rejects_calls_without_callable_check() {
+        let mut library = SourceLibrary::new();
+        let bytes = hand_built(&[OpCode::Constant, OpCode::Call, OpCode::Return], vec![Value::Natural(1)], true);
+
+        assert_eq!(decode(&bytes, &mut library).err(), Some(LoadError::InvalidCode(3)));
+    }
+
+    #[test]
+    fn rejects_failing_instructions_without_span() {
+        let mut library = SourceLibrary::new();
+        let ops = [OpCode::True, OpCode::False, OpCode::Add, OpCode::Return];
+
+        assert!(decode(&hand_built(&ops, vec![], true), &mut library).is_ok());
+        assert_eq!(decode(&hand_built(&ops, vec![], false), &mut library).err(), Some(LoadError::InvalidLineTable));
+    }
+}
@@ -0,0 +1,265 @@
+use rlox_interpreter::RuntimeResult;
+use rlox_interpreter::error;
+use rlox_interpreter::native_functions::NativeFnContext;
+use rlox_interpreter::value_system::{self, Value};
+use rlox_source::SourceMetadata;
+
+use crate::chunk::{Chunk, OpCode};
+
+const STACK_SIZE: usize = 256;
+
+struct Vm<'a> {
+    chunk: &'a Chunk,
+    ip: usize,
+    stack: Vec<Value>,
+}
+
+pub fn run(chunk: &Chunk) -> RuntimeResult<()> {
+    let mut vm = Vm {
+        chunk,
+        ip: 0,
+        stack: Vec::with_capacity(STACK_SIZE),
+    };
+
+    vm.run()
+}
+
+impl Vm<'_> {
+    fn run(&mut self) -> RuntimeResult<()> {
+        loop {
+            let offset = self.ip;
+            let op = self.read_op();
+
+            match op {
+                OpCode::Return => return Ok(()),
+
+                OpCode::Constant => {
+                    let constant = self.read_u16() as usize;
+                    self.stack.push(self.chunk.constants[constant].clone());
+                }
+
+                OpCode::Nil => self.stack.push(Value::Nil),
+                OpCode::True => self.stack.push(Value::Boolean(true)),
+                OpCode::False => self.stack.push(Value::Boolean(false)),
+
+                OpCode::Pop => {
+                    self.pop();
+                }
+
+                OpCode::PopN => {
+                    let count = self.read_u16() as usize;
+                    self.stack.truncate(self.stack.len() - count);
+                }
+
+                OpCode::GetLocal => {
+                    let slot = self.read_u16() as usize;
+                    self.stack.push(self.stack[slot].clone());
+                }
+
+                OpCode::SetLocal => {
+                    let slot = self.read_u16() as usize;
+                    self.stack[slot] = self.pop();
+                    self.stack.push(Value::Nil);
+                }
+
+                OpCode::ShortCircuitAnd => {
+                    let distance = self.read_u16() as usize;
+
+                    if matches!(self.peek(), Value::Boolean(false)) {
+                        self.ip += distance;
+                    }
+                }
+
+                OpCode::ShortCircuitOr => {
+                    let distance = self.read_u16() as usize;
+
+                    if matches!(self.peek(), Value::Boolean(true)) {
+                        self.ip += distance;
+                    }
+                }
+
+                OpCode::Negate | OpCode::Not => self.unary(op, offset)?,
+
+                OpCode::Jump => {
+                    let distance = self.read_u16() as usize;
+                    self.ip += distance;
+                }
+
+                OpCode::JumpIfFalse => {
+                    let distance = self.read_u16() as usize;
+
+                    match self.pop() {
+                        Value::Boolean(true) => (),
+                        Value::Boolean(false) => self.ip += distance,
+                        found => {
+                            let metadata = self.metadata(offset);
+
+                            return Err(From::from(error::UnexpectedValue {
+                                start: metadata.start,
+                                end: metadata.end,
+                                source: metadata.source,
+                                found,
+                            }));
+                        }
+                    }
+                }
+
+                OpCode::Loop => {
+                    let distance = self.read_u16() as usize;
+                    self.ip -= distance;
+                }
+
+                OpCode::Callable => {
+                    if !matches!(self.peek(), Value::Fn(_)) {
+                        let metadata = self.metadata(offset);
+
+                        return Err(From::from(error::UnexpectedValue {
+                            start: metadata.start,
+                            end: metadata.end,
+                            source: metadata.source,
+                            found: self.peek().clone(),
+                        }));
+                    }
+                }
+
+                OpCode::Call => self.call(offset)?,
+
+                OpCode::Undefined => {
+                    let metadata = self.metadata(offset);
+                    let suggestion = match &self.chunk.constants[self.read_u16() as usize] {
+                        Value::String(name) => Some(name.clone()),
+                        _ => None,
+                    };
+
+                    return Err(From::from(error::VarNotFound {
+                        start: metadata.start,
+                        end: metadata.end,
+                        source: metadata.source,
+                        suggestion,
+                    }));
+                }
+
+                OpCode::InvalidAssign => {
+                    let metadata = self.metadata(offset);
+
+                    return Err(From::from(error::InvalidAssign {
+                        start: metadata.start,
+                        end: metadata.end,
+                        source: metadata.source,
+                    }));
+                }
+
+                binary => self.binary(binary, offset)?,
+            }
+        }
+    }
+
+    fn binary(&mut self, op: OpCode, offset: usize) -> RuntimeResult<()> {
+        let Some(operator) = op.binary_operator() else {
+            unreachable!("{op:?} is not a binary operator");
+        };
+
+        let rhs = self.pop();
+        let lhs = self.pop();
+
+        let Ok(result) = value_system::binary_operation(operator, lhs, rhs) else {
+            let metadata = self.metadata(offset);
+
+            return Err(From::from(error::OperationNotDefined {
+                start: metadata.start,
+                end: metadata.end,
+                source: metadata.source,
+            }));
+        };
+
+        self.stack.push(result);
+
+        Ok(())
+    }
+
+    fn unary(&mut self, op: OpCode, offset: usize) -> RuntimeResult<()> {
+        let operand = self.pop();
+
+        let result = match op {
+            OpCode::Negate => value_system::neg(operand),
+            _ => value_system::not(operand),
+        };
+
+        let Ok(result) = result else {
+            let metadata = self.metadata(offset);
+
+            return Err(From::from(error::OperationNotDefined {
+                start: metadata.start,
+                end: metadata.end,
+                source: metadata.source,
+            }));
+        };
+
+        self.stack.push(result);
+
+        Ok(())
+    }
+
+    fn call(&mut self, offset: usize) -> RuntimeResult<()> {
+        let arguments = self.read_u8() as usize;
+        let args = self.stack.split_off(self.stack.len() - arguments);
+
+        // `Callable` already checked the callee.
+        let Value::Fn(callee) = self.pop() else {
+            unreachable!("Calls are always preceded by a callable check");
+        };
+
+        let context = NativeFnContext {
+            args,
+            caller: self.metadata(offset),
+        };
+
+        let result = (callee.function)(context)?;
+        self.stack.push(result);
+
+        Ok(())
+    }
+
+    fn metadata(&self, offset: usize) -> SourceMetadata {
+        let Some(metadata) = self.chunk.metadata(offset) else {
+            panic!("Instruction at {offset} can fail but has no metadata");
+        };
+
+        metadata
+    }
+
+    fn read_op(&mut self) -> OpCode {
+        let byte = self.chunk.code[self.ip];
+        self.ip += 1;
+
+        let Ok(op) = OpCode::try_from(byte) else {
+            panic!("Invalid opcode {byte} at {}", self.ip - 1);
+        };
+
+        op
+    }
+
+    fn read_u8(&mut self) -> u8 {
+        let operand = self.chunk.code[self.ip];
+        self.ip += 1;
+
+        operand
+    }
+
+    fn read_u16(&mut self) -> u16 {
+        let operand = self.chunk.read_u16(self.ip);
+        self.ip += 2;
+
+        operand
+    }
+
+    fn peek(&self) -> &Value {
+        // Unwrapping is safe since the compiler only emits
+        // balanced stack operations.
+        self.stack.last().unwrap()
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap()
+    }
+}